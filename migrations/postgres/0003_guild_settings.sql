@@ -0,0 +1,6 @@
+-- Per-guild settings, such as whether NSFW content is allowed.
+
+CREATE TABLE IF NOT EXISTS guild_settings (
+    server_id TEXT PRIMARY KEY NOT NULL,
+    nsfw_enabled BOOLEAN NOT NULL DEFAULT TRUE
+);
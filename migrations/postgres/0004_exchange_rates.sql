@@ -0,0 +1,14 @@
+-- Cached exchange rates and the supported currencies of `convert`.
+
+CREATE TABLE IF NOT EXISTS exchange_rates (
+    base TEXT NOT NULL,
+    target TEXT NOT NULL,
+    rate DOUBLE PRECISION NOT NULL,
+    fetched_at BIGINT NOT NULL,
+    PRIMARY KEY (base, target)
+);
+
+CREATE TABLE IF NOT EXISTS currencies (
+    code TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL
+);
@@ -0,0 +1,7 @@
+-- Guilds can have several prefixes, but each of them only once. Duplicates
+-- are dropped before the constraint is added, keeping the oldest row.
+
+DELETE FROM prefixes
+WHERE id NOT IN (SELECT MIN(id) FROM prefixes GROUP BY server_id, prefix);
+
+CREATE UNIQUE INDEX IF NOT EXISTS prefixes_server_id_prefix ON prefixes (server_id, prefix);
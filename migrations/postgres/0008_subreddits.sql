@@ -0,0 +1,14 @@
+-- When each subreddit was last requested through `reddit` and when its posts
+-- were last fetched. Subreddits nobody requested for a while are no longer
+-- refreshed, and a fetch without any image posts is remembered as well.
+
+CREATE TABLE IF NOT EXISTS subreddits (
+    name TEXT PRIMARY KEY NOT NULL,
+    requested_at BIGINT NOT NULL,
+    fetched_at BIGINT
+);
+
+INSERT INTO subreddits (name, requested_at, fetched_at)
+SELECT DISTINCT subreddit, EXTRACT(EPOCH FROM NOW())::BIGINT, EXTRACT(EPOCH FROM NOW())::BIGINT
+FROM reddit_posts
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,52 @@
+-- The tables of the core features, as they were before there were
+-- migrations. They are only created if they don't exist yet, so existing
+-- databases can adopt this baseline. Later changes to these tables must be
+-- made in their own migrations.
+
+CREATE TABLE IF NOT EXISTS error_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    server TEXT,
+    channel TEXT NOT NULL,
+    user TEXT NOT NULL,
+    command TEXT,
+    stack TEXT,
+    timestamp INTEGER,
+    log TEXT,
+    error TEXT
+);
+
+CREATE TABLE IF NOT EXISTS help_messages (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    cmd TEXT NOT NULL UNIQUE,
+    desc TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS leet (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    source TEXT NOT NULL,
+    translated TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS ai_command_aliases (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    command TEXT NOT NULL,
+    alias TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS ai_reactions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    command TEXT NOT NULL,
+    reaction TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS prefixes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    server_id TEXT NOT NULL,
+    prefix TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS statuses (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    type TEXT NOT NULL,
+    status TEXT NOT NULL
+);
@@ -0,0 +1,11 @@
+-- Cached hot image posts of the subreddits requested through `reddit`.
+
+CREATE TABLE IF NOT EXISTS reddit_posts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    subreddit TEXT NOT NULL,
+    title TEXT NOT NULL,
+    url TEXT NOT NULL,
+    over_18 BOOLEAN NOT NULL,
+    permalink TEXT NOT NULL,
+    UNIQUE (subreddit, url)
+);
@@ -0,0 +1,7 @@
+-- The weight of a status in the rotation and when it may be shown. The days
+-- are a comma separated list of weekdays, the times are unix timestamps.
+
+ALTER TABLE statuses ADD COLUMN weight INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE statuses ADD COLUMN active_days TEXT;
+ALTER TABLE statuses ADD COLUMN starts_at INTEGER;
+ALTER TABLE statuses ADD COLUMN ends_at INTEGER;
@@ -0,0 +1,7 @@
+-- Every alias may only belong to a single reaction command. Duplicates are
+-- dropped before the constraint is added, keeping the oldest row.
+
+DELETE FROM ai_command_aliases
+WHERE id NOT IN (SELECT MIN(id) FROM ai_command_aliases GROUP BY alias);
+
+CREATE UNIQUE INDEX IF NOT EXISTS ai_command_aliases_alias ON ai_command_aliases (alias);
@@ -0,0 +1,13 @@
+-- When each subreddit was last requested through `reddit` and when its posts
+-- were last fetched. Subreddits nobody requested for a while are no longer
+-- refreshed, and a fetch without any image posts is remembered as well.
+
+CREATE TABLE IF NOT EXISTS subreddits (
+    name TEXT PRIMARY KEY NOT NULL,
+    requested_at INTEGER NOT NULL,
+    fetched_at INTEGER
+);
+
+INSERT OR IGNORE INTO subreddits (name, requested_at, fetched_at)
+SELECT DISTINCT subreddit, CAST(strftime('%s', 'now') AS INTEGER), CAST(strftime('%s', 'now') AS INTEGER)
+FROM reddit_posts;
@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{config::Config, db::repositories::ExchangeRepository};
+
+/// Common currency symbols and the codes they stand for. Symbols shared by
+/// several currencies map to the most common one, e.g. `$` to US dollars.
+const SYMBOLS: &[(&str, &str)] = &[
+    ("$", "USD"),
+    ("us$", "USD"),
+    ("€", "EUR"),
+    ("£", "GBP"),
+    ("¥", "JPY"),
+    ("₹", "INR"),
+    ("₩", "KRW"),
+    ("₽", "RUB"),
+    ("₺", "TRY"),
+    ("₴", "UAH"),
+    ("₱", "PHP"),
+    ("₪", "ILS"),
+    ("₫", "VND"),
+    ("฿", "THB"),
+    ("zł", "PLN"),
+    ("a$", "AUD"),
+    ("c$", "CAD"),
+    ("nz$", "NZD"),
+    ("hk$", "HKD"),
+    ("r$", "BRL"),
+];
+
+#[derive(Deserialize)]
+struct RatesResponse {
+    result: String,
+    #[serde(rename = "error-type")]
+    error_type: Option<String>,
+    #[serde(default)]
+    conversion_rates: HashMap<String, f64>,
+}
+
+#[derive(Deserialize)]
+struct CodesResponse {
+    result: String,
+    #[serde(rename = "error-type")]
+    error_type: Option<String>,
+    #[serde(default)]
+    supported_codes: Vec<(String, String)>,
+}
+
+/// A client for the exchange rate API that caches the rates in the database,
+/// so every base currency is only requested once per `exchange_cache_ttl`.
+/// The API URL and key are read from the config on every request, so they
+/// can be changed by reloading it.
+pub struct ExchangeClient {
+    http: reqwest::Client,
+}
+
+impl ExchangeClient {
+    pub fn new() -> Self {
+        Self {
+            http: reqwest::Client::new(),
+        }
+    }
+
+    fn url(config: &Config, path: &str) -> String {
+        format!(
+            "{}/{}/{path}",
+            config.exchange_api_url.trim_end_matches('/'),
+            config.exchange_api_key
+        )
+    }
+
+    /// Requests the path from the API. The key is part of the URL, so the URL
+    /// is stripped from request errors before they reach replies or logs.
+    async fn get<T: DeserializeOwned>(&self, config: &Config, path: &str) -> Result<T> {
+        let response = self
+            .http
+            .get(Self::url(config, path))
+            .send()
+            .await
+            .map_err(reqwest::Error::without_url)?
+            .json::<T>()
+            .await
+            .map_err(reqwest::Error::without_url)?;
+
+        Ok(response)
+    }
+
+    /// Returns the rate to convert from one currency to another, fetching
+    /// all rates of the `from` currency if the cached ones are missing or
+    /// older than the cache TTL.
+    ///
+    /// # Errors
+    /// * If fetching the rates or accessing the database fails.
+    /// * If the API doesn't know one of the currencies.
+    pub async fn rate(
+        &self,
+        config: &Config,
+        db: &dyn ExchangeRepository,
+        from: &str,
+        to: &str,
+    ) -> Result<f64> {
+        if from == to {
+            return Ok(1.0);
+        }
+
+        let oldest = Utc::now().timestamp() - config.exchange_cache_ttl;
+
+        if let Some(rate) = db.cached_rate(from, to, oldest).await? {
+            return Ok(rate);
+        }
+
+        let rates = self.fetch_rates(config, from).await?;
+        let rate = *rates
+            .get(to)
+            .ok_or_else(|| anyhow!("Unknown currency `{to}`"))?;
+
+        db.store_rates(from, &rates, Utc::now().timestamp()).await?;
+
+        Ok(rate)
+    }
+
+    async fn fetch_rates(&self, config: &Config, base: &str) -> Result<HashMap<String, f64>> {
+        let response = self
+            .get::<RatesResponse>(config, &format!("latest/{base}"))
+            .await?;
+
+        match response.result.as_str() {
+            "success" => Ok(response.conversion_rates),
+            _ => match response.error_type.as_deref() {
+                Some("unsupported-code") => Err(anyhow!("Unknown currency `{base}`")),
+                Some(error) => Err(anyhow!("Failed to fetch exchange rates: {error}")),
+                None => Err(anyhow!("Failed to fetch exchange rates")),
+            },
+        }
+    }
+
+    /// Returns all supported currencies as code and name pairs, sorted by
+    /// code. They rarely change, so they are only fetched once and then
+    /// served from the database.
+    ///
+    /// # Errors
+    /// * If fetching the currencies or accessing the database fails.
+    pub async fn currencies(
+        &self,
+        config: &Config,
+        db: &dyn ExchangeRepository,
+    ) -> Result<Vec<(String, String)>> {
+        let cached = db.list_currencies().await?;
+
+        if !cached.is_empty() {
+            return Ok(cached);
+        }
+
+        let response = self.get::<CodesResponse>(config, "codes").await?;
+
+        if response.result != "success" {
+            return Err(anyhow!(
+                "Failed to fetch currencies: {}",
+                response.error_type.unwrap_or_default()
+            ));
+        }
+
+        db.store_currencies(&response.supported_codes).await?;
+
+        let mut currencies = response.supported_codes;
+        currencies.sort();
+
+        Ok(currencies)
+    }
+}
+
+/// Turns a currency code or one of the common symbols into an uppercase
+/// currency code, e.g. `usd` or `$` into `USD`.
+///
+/// # Returns
+/// The currency code, or `None` if the input is neither a symbol nor looks
+/// like a three letter code.
+pub fn parse_currency(input: &str) -> Option<String> {
+    let input = input.trim().to_lowercase();
+
+    if let Some((_, code)) = SYMBOLS.iter().find(|(symbol, _)| *symbol == input) {
+        return Some((*code).to_string());
+    }
+
+    (input.len() == 3 && input.chars().all(|c| c.is_ascii_alphabetic()))
+        .then(|| input.to_uppercase())
+}
+
+/// Parses an amount like `12.50` or `1,000`, optionally prefixed or suffixed
+/// with a currency symbol like `$12.50` or `12€`.
+///
+/// # Returns
+/// The amount and the currency of the symbol, if there was one.
+pub fn parse_amount(input: &str) -> Option<(f64, Option<String>)> {
+    let start = input.find(|c: char| c.is_ascii_digit() || c == '.')?;
+    let end = input.rfind(|c: char| c.is_ascii_digit() || c == '.')? + 1;
+
+    let amount = input[start..end].replace(',', "").parse::<f64>().ok()?;
+    if !amount.is_finite() {
+        return None;
+    }
+
+    let symbol = format!("{}{}", &input[..start], &input[end..]);
+    if symbol.is_empty() {
+        return Some((amount, None));
+    }
+
+    parse_currency(&symbol).map(|code| (amount, Some(code)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        apis::stub::{StubRequest, StubServer},
+        db::memory::MemoryDatabase,
+    };
+
+    fn config(url: &str, ttl: i64) -> Config {
+        let toml = format!(
+            "bot_token = \"token\"\nbot_owner = 1\nlog_channel = 1\n\
+            exchange_api_key = \"key\"\nexchange_api_url = \"{url}/\"\nexchange_cache_ttl = {ttl}"
+        );
+        Config::from_toml(&toml, &[]).unwrap()
+    }
+
+    fn api(request: &StubRequest) -> (u16, String) {
+        let body = match request.path.as_str() {
+            "/key/latest/USD" => {
+                r#"{"result": "success", "conversion_rates": {"USD": 1, "EUR": 0.9}}"#
+            }
+            "/key/codes" => {
+                r#"{"result": "success", "supported_codes": [["USD", "US Dollar"], ["EUR", "Euro"]]}"#
+            }
+            _ => r#"{"result": "error", "error-type": "unsupported-code"}"#,
+        };
+        (200, body.to_string())
+    }
+
+    #[test]
+    fn parses_codes_and_symbols() {
+        assert_eq!(parse_currency("usd").as_deref(), Some("USD"));
+        assert_eq!(parse_currency(" Eur ").as_deref(), Some("EUR"));
+        assert_eq!(parse_currency("€").as_deref(), Some("EUR"));
+        assert_eq!(parse_currency("US$").as_deref(), Some("USD"));
+        assert_eq!(parse_currency("zł").as_deref(), Some("PLN"));
+
+        for input in ["", "us", "euro", "u5d", "€€"] {
+            assert_eq!(parse_currency(input), None, "{input}");
+        }
+    }
+
+    #[test]
+    fn every_symbol_maps_to_a_code() {
+        for (symbol, code) in SYMBOLS {
+            assert_eq!(symbol.to_lowercase(), *symbol);
+            assert_eq!(parse_currency(symbol).as_deref(), Some(*code));
+        }
+    }
+
+    #[test]
+    fn parses_amounts() {
+        assert_eq!(parse_amount("12.50"), Some((12.5, None)));
+        assert_eq!(parse_amount("1,000"), Some((1000.0, None)));
+        assert_eq!(parse_amount(".5"), Some((0.5, None)));
+        assert_eq!(
+            parse_amount("$12.50"),
+            Some((12.5, Some("USD".to_string())))
+        );
+        assert_eq!(parse_amount("12€"), Some((12.0, Some("EUR".to_string()))));
+        assert_eq!(parse_amount("c$3"), Some((3.0, Some("CAD".to_string()))));
+        assert_eq!(parse_amount("5usd"), Some((5.0, Some("USD".to_string()))));
+
+        for input in ["", "abc", "1.2.3", "5xx", "$", "1..5"] {
+            assert_eq!(parse_amount(input), None, "{input}");
+        }
+    }
+
+    #[tokio::test]
+    async fn caches_rates_until_they_expire() {
+        let stub = StubServer::start(api).await;
+        let client = ExchangeClient::new();
+        let db = MemoryDatabase::default();
+
+        let cached = config(&stub.url, 60);
+        assert_eq!(client.rate(&cached, &db, "USD", "EUR").await.unwrap(), 0.9);
+        assert_eq!(client.rate(&cached, &db, "USD", "EUR").await.unwrap(), 0.9);
+        assert_eq!(client.rate(&cached, &db, "USD", "USD").await.unwrap(), 1.0);
+        assert_eq!(stub.requests().len(), 1);
+        assert_eq!(stub.requests()[0].method, "GET");
+        assert_eq!(stub.requests()[0].path, "/key/latest/USD");
+
+        // Rates stored in the same second are already too old without a TTL.
+        let expired = config(&stub.url, 0);
+        assert_eq!(client.rate(&expired, &db, "USD", "EUR").await.unwrap(), 0.9);
+        assert_eq!(stub.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn reports_unknown_currencies() {
+        let stub = StubServer::start(api).await;
+        let client = ExchangeClient::new();
+        let db = MemoryDatabase::default();
+        let config = config(&stub.url, 60);
+
+        let error = client.rate(&config, &db, "XXX", "EUR").await.unwrap_err();
+        assert_eq!(error.to_string(), "Unknown currency `XXX`");
+
+        let error = client.rate(&config, &db, "USD", "XXX").await.unwrap_err();
+        assert_eq!(error.to_string(), "Unknown currency `XXX`");
+    }
+
+    #[tokio::test]
+    async fn fetches_currencies_once() {
+        let stub = StubServer::start(api).await;
+        let client = ExchangeClient::new();
+        let db = MemoryDatabase::default();
+        let config = config(&stub.url, 60);
+
+        let expected = [
+            ("EUR".to_string(), "Euro".to_string()),
+            ("USD".to_string(), "US Dollar".to_string()),
+        ];
+        assert_eq!(client.currencies(&config, &db).await.unwrap(), expected);
+        assert_eq!(client.currencies(&config, &db).await.unwrap(), expected);
+        assert_eq!(stub.requests().len(), 1);
+    }
+}
@@ -0,0 +1,162 @@
+use anyhow::{anyhow, Result};
+use reqwest::header;
+use serde::Deserialize;
+
+use crate::config::Config;
+
+/// The largest image Imgur accepts without an account, in bytes.
+pub const MAX_IMAGE_SIZE: u64 = 20 * 1024 * 1024;
+/// The content types Imgur accepts as images.
+pub const IMAGE_TYPES: &[&str] = &[
+    "image/jpeg",
+    "image/png",
+    "image/gif",
+    "image/apng",
+    "image/tiff",
+    "image/webp",
+];
+
+#[derive(Deserialize)]
+struct UploadResponse {
+    data: UploadData,
+    success: bool,
+}
+
+#[derive(Deserialize)]
+struct UploadData {
+    link: Option<String>,
+    error: Option<serde_json::Value>,
+}
+
+/// Checks if the content type is one of the [`IMAGE_TYPES`], ignoring any
+/// parameters like `; charset=...`.
+pub fn is_image_type(content_type: &str) -> bool {
+    let content_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    IMAGE_TYPES.contains(&content_type.as_str())
+}
+
+/// A client for anonymous uploads to Imgur. The API URL and client Id are
+/// read from the config on every upload.
+pub struct ImgurClient {
+    http: reqwest::Client,
+}
+
+impl ImgurClient {
+    pub fn new() -> Self {
+        Self {
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Lets Imgur fetch and upload the image at the URL. The bot never
+    /// downloads the image itself, so links to private addresses can't be
+    /// used to reach anything only the bot can reach.
+    ///
+    /// # Returns
+    /// The permanent link of the uploaded image.
+    ///
+    /// # Errors
+    /// * If the upload fails or Imgur rejects the image.
+    pub async fn upload_url(&self, config: &Config, url: &str) -> Result<String> {
+        let response = self
+            .http
+            .post(format!(
+                "{}/3/image",
+                config.imgur_api_url.trim_end_matches('/')
+            ))
+            .header(
+                header::AUTHORIZATION,
+                format!("Client-ID {}", config.imgur_client_id),
+            )
+            .form(&[("image", url), ("type", "url")])
+            .send()
+            .await?
+            .json::<UploadResponse>()
+            .await?;
+
+        match (response.success, response.data.link) {
+            (true, Some(link)) => Ok(link),
+            _ => Err(anyhow!(
+                "Imgur rejected the upload: {}",
+                response
+                    .data
+                    .error
+                    .map_or("unknown error".to_string(), |error| error.to_string())
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::stub::{StubRequest, StubServer};
+
+    fn api(request: &StubRequest) -> (u16, String) {
+        if request.body.contains("private") {
+            return (
+                400,
+                r#"{"data": {"error": "Invalid URL"}, "success": false, "status": 400}"#
+                    .to_string(),
+            );
+        }
+        (
+            200,
+            r#"{"data": {"link": "https://i.imgur.com/abc.png"}, "success": true, "status": 200}"#
+                .to_string(),
+        )
+    }
+
+    #[test]
+    fn recognizes_image_types() {
+        for content_type in ["image/png", "IMAGE/JPEG", "image/webp; charset=binary"] {
+            assert!(is_image_type(content_type), "{content_type}");
+        }
+        for content_type in ["", "image/svg+xml", "text/plain", "video/mp4", "image"] {
+            assert!(!is_image_type(content_type), "{content_type}");
+        }
+    }
+
+    #[tokio::test]
+    async fn uploads_links() {
+        let stub = StubServer::start(api).await;
+        let toml = format!(
+            "bot_token = \"token\"\nbot_owner = 1\nlog_channel = 1\n\
+            imgur_client_id = \"client\"\nimgur_api_url = \"{}\"",
+            stub.url
+        );
+        let config = Config::from_toml(&toml, &[]).unwrap();
+        let client = ImgurClient::new();
+
+        let link = client
+            .upload_url(&config, "https://example.com/a.png")
+            .await
+            .unwrap();
+        assert_eq!(link, "https://i.imgur.com/abc.png");
+
+        let error = client
+            .upload_url(&config, "http://private/a.png")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Imgur rejected the upload: \"Invalid URL\""
+        );
+
+        let requests = stub.requests();
+        assert_eq!(requests[0].path, "/3/image");
+        assert_eq!(
+            requests[0].header("authorization"),
+            Some("Client-ID client")
+        );
+        assert_eq!(
+            requests[0].body,
+            "image=https%3A%2F%2Fexample.com%2Fa.png&type=url"
+        );
+    }
+}
@@ -0,0 +1,5 @@
+pub mod exchange;
+pub mod imgur;
+pub mod reddit;
+#[cfg(test)]
+pub mod stub;
@@ -0,0 +1,448 @@
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use serde::Deserialize;
+use tokio::{
+    sync::Mutex,
+    time::{sleep, Duration, Instant},
+};
+
+use crate::{
+    config::{Config, Integration},
+    db::models::RedditPost,
+    helpers::types::Handler,
+};
+
+const USER_AGENT: &str = concat!("hifumi-rs/", env!("CARGO_PKG_VERSION"));
+/// How often the cached posts of every subreddit are refreshed.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// How long a subreddit is kept refreshed after it was last requested.
+const SUBREDDIT_EXPIRY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// How many hot posts are requested per subreddit.
+const POST_LIMIT: u32 = 100;
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+#[derive(Deserialize)]
+struct Listing {
+    data: ListingData,
+}
+
+#[derive(Deserialize)]
+struct ListingData {
+    children: Vec<ListingChild>,
+}
+
+#[derive(Deserialize)]
+struct ListingChild {
+    data: ListingPost,
+}
+
+#[derive(Deserialize)]
+struct ListingPost {
+    title: String,
+    url: String,
+    over_18: bool,
+    permalink: String,
+    #[serde(default)]
+    is_self: bool,
+    post_hint: Option<String>,
+}
+
+impl ListingPost {
+    fn is_image(&self) -> bool {
+        if self.is_self {
+            return false;
+        }
+        self.post_hint.as_deref() == Some("image")
+            || [".jpg", ".jpeg", ".png", ".gif", ".webp"]
+                .iter()
+                .any(|ext| self.url.to_lowercase().ends_with(ext))
+    }
+}
+
+struct AccessToken {
+    token: String,
+    expires_at: Instant,
+}
+
+/// A small client for the parts of the Reddit API the bot needs. It
+/// authenticates with the refresh token from the config and keeps the access
+/// token around until shortly before it expires.
+pub struct RedditClient {
+    http: reqwest::Client,
+    token: Mutex<Option<AccessToken>>,
+}
+
+impl RedditClient {
+    pub fn new() -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            token: Mutex::new(None),
+        }
+    }
+
+    /// Forgets the current access token, so the next request authenticates
+    /// again, e.g. after the credentials were changed.
+    pub async fn clear_token(&self) {
+        *self.token.lock().await = None;
+    }
+
+    /// Returns a valid access token, requesting a new one if there is none
+    /// yet or the current one is about to expire.
+    async fn access_token(&self, config: &Config) -> Result<String> {
+        let mut token = self.token.lock().await;
+
+        if let Some(token) = token.as_ref() {
+            if token.expires_at > Instant::now() {
+                return Ok(token.token.clone());
+            }
+        }
+
+        let response = self
+            .http
+            .post(&config.reddit_auth_url)
+            .basic_auth(&config.reddit_client_id, Some(&config.reddit_client_secret))
+            .header(reqwest::header::USER_AGENT, USER_AGENT)
+            .form(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", &config.reddit_refresh_token),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<TokenResponse>()
+            .await?;
+
+        // Refresh a minute early so a token never expires mid-request.
+        let lifetime = Duration::from_secs(response.expires_in.saturating_sub(60));
+        *token = Some(AccessToken {
+            token: response.access_token.clone(),
+            expires_at: Instant::now() + lifetime,
+        });
+
+        Ok(response.access_token)
+    }
+
+    /// Fetches the current hot image posts of the subreddit.
+    ///
+    /// # Errors
+    /// * If authenticating or the request fails, or the subreddit doesn't
+    ///   exist.
+    pub async fn fetch_hot_images(
+        &self,
+        config: &Config,
+        subreddit: &str,
+    ) -> Result<Vec<RedditPost>> {
+        let token = self.access_token(config).await?;
+
+        let listing = self
+            .http
+            .get(format!(
+                "{}/r/{subreddit}/hot",
+                config.reddit_api_url.trim_end_matches('/')
+            ))
+            .query(&[
+                ("limit", POST_LIMIT.to_string()),
+                ("raw_json", "1".to_string()),
+            ])
+            .bearer_auth(token)
+            .header(reqwest::header::USER_AGENT, USER_AGENT)
+            .send()
+            .await?
+            .error_for_status()
+            .map_err(|_| anyhow!("Couldn't fetch posts from r/{subreddit}"))?
+            .json::<Listing>()
+            .await?;
+
+        let posts = listing
+            .data
+            .children
+            .into_iter()
+            .map(|child| child.data)
+            .filter(ListingPost::is_image)
+            .map(|post| RedditPost {
+                id: 0,
+                subreddit: subreddit.to_string(),
+                title: post.title,
+                url: post.url,
+                over_18: post.over_18,
+                permalink: format!("https://reddit.com{}", post.permalink),
+            })
+            .collect();
+
+        Ok(posts)
+    }
+}
+
+/// Turns a subreddit as users write it, like `r/Aww` or `/r/aww`, into its
+/// lowercase name.
+pub fn normalize_subreddit(input: &str) -> String {
+    let name = input.trim().to_lowercase();
+    let name = name.strip_prefix('/').unwrap_or(&name);
+    name.strip_prefix("r/").unwrap_or(name).to_string()
+}
+
+/// Checks if the name is a valid subreddit name, so it can safely be put
+/// into a URL.
+pub fn is_valid_subreddit(name: &str) -> bool {
+    (2..=21).contains(&name.len()) && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Fetches the hot image posts of the subreddit and replaces its cached
+/// posts with them.
+///
+/// # Returns
+/// The number of cached posts.
+///
+/// # Errors
+/// * If fetching the posts or updating the database fails.
+pub async fn refresh_subreddit(handler: &Handler, subreddit: &str) -> Result<usize> {
+    let posts = handler
+        .reddit
+        .fetch_hot_images(&handler.config(), subreddit)
+        .await?;
+
+    handler
+        .db
+        .replace_reddit_posts(subreddit, &posts, Utc::now().timestamp())
+        .await?;
+
+    Ok(posts.len())
+}
+
+/// Checks if the posts of the subreddit were fetched within the refresh
+/// interval. This is also the case if there were no image posts, so
+/// subreddits without any aren't fetched on every request.
+///
+/// # Errors
+/// * If the database query fails.
+pub async fn is_recently_refreshed(handler: &Handler, subreddit: &str) -> Result<bool> {
+    let fetched_at = handler.db.subreddit_fetched_at(subreddit).await?;
+    let refreshed_after = Utc::now().timestamp() - duration_secs(REFRESH_INTERVAL);
+
+    Ok(fetched_at.is_some_and(|fetched_at| fetched_at > refreshed_after))
+}
+
+fn duration_secs(duration: Duration) -> i64 {
+    i64::try_from(duration.as_secs()).unwrap_or(i64::MAX)
+}
+
+/// Periodically refreshes the cached posts of the subreddits requested within
+/// [`SUBREDDIT_EXPIRY`], so the `reddit` command can serve posts from the
+/// cache. Subreddits requested before that are forgotten. Refreshing is
+/// skipped while the Reddit integration isn't configured.
+pub async fn start_reddit_refresh_loop(handler: &Handler) {
+    loop {
+        sleep(REFRESH_INTERVAL).await;
+
+        if !handler.config().is_enabled(Integration::Reddit) {
+            continue;
+        }
+
+        let requested_after = Utc::now().timestamp() - duration_secs(SUBREDDIT_EXPIRY);
+
+        match handler.db.expire_subreddits(requested_after).await {
+            Ok(0) => {}
+            Ok(count) => debug!("Forgot {count} subreddits that weren't requested recently"),
+            Err(e) => error!("Failed to expire subreddits: {e}"),
+        }
+
+        let subreddits = match handler.db.cached_subreddits(requested_after).await {
+            Ok(subreddits) => subreddits,
+            Err(e) => {
+                error!("Failed to load cached subreddits: {e}");
+                continue;
+            }
+        };
+
+        for subreddit in subreddits {
+            match refresh_subreddit(handler, &subreddit).await {
+                Ok(count) => debug!("Refreshed {count} posts from r/{subreddit}"),
+                Err(e) => warn!("Failed to refresh r/{subreddit}: {e}"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::stub::{StubRequest, StubServer};
+
+    fn config(url: &str) -> Config {
+        let toml = format!(
+            "bot_token = \"token\"\nbot_owner = 1\nlog_channel = 1\n\
+            reddit_client_id = \"id\"\nreddit_client_secret = \"secret\"\n\
+            reddit_refresh_token = \"refresh\"\n\
+            reddit_api_url = \"{url}\"\nreddit_auth_url = \"{url}/token\""
+        );
+        Config::from_toml(&toml, &[]).unwrap()
+    }
+
+    fn post(url: &str, is_self: bool, post_hint: Option<&str>) -> ListingPost {
+        ListingPost {
+            title: "Title".to_string(),
+            url: url.to_string(),
+            over_18: false,
+            permalink: "/r/aww/comments/1/title/".to_string(),
+            is_self,
+            post_hint: post_hint.map(ToString::to_string),
+        }
+    }
+
+    fn api(request: &StubRequest) -> (u16, String) {
+        match request.path.as_str() {
+            "/token" => (
+                200,
+                r#"{"access_token": "access", "expires_in": 3600}"#.to_string(),
+            ),
+            "/r/aww/hot?limit=100&raw_json=1" => (
+                200,
+                r#"{"data": {"children": [
+                    {"data": {"title": "Cat", "url": "https://i.redd.it/cat.jpg", "over_18": false, "permalink": "/r/aww/comments/1/cat/"}},
+                    {"data": {"title": "Question", "url": "https://reddit.com/r/aww/comments/2/", "over_18": false, "permalink": "/r/aww/comments/2/question/", "is_self": true}},
+                    {"data": {"title": "Article", "url": "https://example.com/article", "over_18": true, "permalink": "/r/aww/comments/3/article/", "post_hint": "link"}}
+                ]}}"#
+                    .to_string(),
+            ),
+            _ => (404, r#"{"message": "Not Found", "error": 404}"#.to_string()),
+        }
+    }
+
+    /// Answers token requests with tokens that already expired.
+    fn expiring_api(request: &StubRequest) -> (u16, String) {
+        match request.path.as_str() {
+            "/token" => (
+                200,
+                r#"{"access_token": "access", "expires_in": 30}"#.to_string(),
+            ),
+            _ => api(request),
+        }
+    }
+
+    #[test]
+    fn normalizes_subreddits() {
+        assert_eq!(normalize_subreddit("aww"), "aww");
+        assert_eq!(normalize_subreddit("r/EarthPorn"), "earthporn");
+        assert_eq!(normalize_subreddit("R/aww"), "aww");
+        assert_eq!(normalize_subreddit(" /r/pics "), "pics");
+        assert_eq!(normalize_subreddit("r/r/pics"), "r/pics");
+    }
+
+    #[test]
+    fn validates_subreddit_names() {
+        assert!(is_valid_subreddit("aww"));
+        assert!(is_valid_subreddit("Earth_Porn"));
+        assert!(is_valid_subreddit(&"a".repeat(21)));
+
+        for name in [
+            "",
+            "a",
+            "a-b",
+            "../aww",
+            "aww?limit=1",
+            "ünicode",
+            &"a".repeat(22),
+        ] {
+            assert!(!is_valid_subreddit(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn recognizes_image_posts() {
+        assert!(post("https://i.redd.it/cat.jpg", false, None).is_image());
+        assert!(post("https://i.imgur.com/CAT.PNG", false, None).is_image());
+        assert!(post("https://example.com/cat", false, Some("image")).is_image());
+
+        assert!(!post("https://i.redd.it/cat.jpg", true, Some("image")).is_image());
+        assert!(!post("https://example.com/cat.jpg.html", false, None).is_image());
+        assert!(!post("https://v.redd.it/video", false, Some("hosted:video")).is_image());
+    }
+
+    #[tokio::test]
+    async fn fetches_hot_images_with_a_cached_token() {
+        let stub = StubServer::start(api).await;
+        let client = RedditClient::new();
+        let config = config(&stub.url);
+
+        let posts = client.fetch_hot_images(&config, "aww").await.unwrap();
+        assert_eq!(posts.len(), 1);
+        assert_eq!(posts[0].subreddit, "aww");
+        assert_eq!(posts[0].url, "https://i.redd.it/cat.jpg");
+        assert_eq!(
+            posts[0].permalink,
+            "https://reddit.com/r/aww/comments/1/cat/"
+        );
+
+        client.fetch_hot_images(&config, "aww").await.unwrap();
+
+        let requests = stub.requests();
+        let paths = requests
+            .iter()
+            .map(|request| request.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                "/token",
+                "/r/aww/hot?limit=100&raw_json=1",
+                "/r/aww/hot?limit=100&raw_json=1"
+            ]
+        );
+
+        let token = &requests[0];
+        assert_eq!(token.method, "POST");
+        assert_eq!(token.header("authorization"), Some("Basic aWQ6c2VjcmV0"));
+        assert_eq!(token.body, "grant_type=refresh_token&refresh_token=refresh");
+        assert_eq!(requests[1].header("authorization"), Some("Bearer access"));
+        assert_eq!(requests[1].header("user-agent"), Some(USER_AGENT));
+    }
+
+    #[tokio::test]
+    async fn refreshes_expiring_and_cleared_tokens() {
+        let client = RedditClient::new();
+
+        let expiring = StubServer::start(expiring_api).await;
+        let expiring_config = config(&expiring.url);
+        client
+            .fetch_hot_images(&expiring_config, "aww")
+            .await
+            .unwrap();
+        client
+            .fetch_hot_images(&expiring_config, "aww")
+            .await
+            .unwrap();
+        assert_eq!(expiring.requests().len(), 4);
+
+        let stub = StubServer::start(api).await;
+        let config = config(&stub.url);
+
+        client.fetch_hot_images(&config, "aww").await.unwrap();
+        client.clear_token().await;
+        client.fetch_hot_images(&config, "aww").await.unwrap();
+
+        let tokens = stub
+            .requests()
+            .iter()
+            .filter(|request| request.path == "/token")
+            .count();
+        assert_eq!(tokens, 2);
+    }
+
+    #[tokio::test]
+    async fn reports_missing_subreddits() {
+        let stub = StubServer::start(api).await;
+        let client = RedditClient::new();
+
+        let error = client
+            .fetch_hot_images(&config(&stub.url), "missing")
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "Couldn't fetch posts from r/missing");
+    }
+}
@@ -0,0 +1,115 @@
+//! Maintenance commands that run instead of the bot when the binary is
+//! started with arguments, e.g. `hifumi-rs restore backups/hifumi-20240101-000000.db`.
+
+use std::{env, fs, path::Path};
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::db::{self, backup, models::BotContent};
+
+const USAGE: &str = "Usage: hifumi-rs [restore <file> | export <file> | import <file>]";
+
+/// Runs the command given by the arguments, without the binary name.
+///
+/// # Errors
+/// * If the command is unknown or fails.
+pub async fn run(args: &[String]) -> Result<()> {
+    match args {
+        [command, file] if command == "restore" => restore(Path::new(file)).await,
+        [command, file] if command == "export" => export(Path::new(file)).await,
+        [command, file] if command == "import" => import(Path::new(file)).await,
+        _ => bail!("{USAGE}"),
+    }
+}
+
+/// Replaces the database at `DATABASE_URL` with the snapshot. The bot has to
+/// be stopped while doing so.
+async fn restore(snapshot: &Path) -> Result<()> {
+    let version = backup::restore(&env::var("DATABASE_URL")?, snapshot).await?;
+    info!(
+        "Restored {} with schema version {version}, restart the bot to use it",
+        snapshot.display()
+    );
+
+    Ok(())
+}
+
+/// Writes the statuses, help messages, leet translations, AI reactions and
+/// aliases and prefixes to the file as JSON, sorted by Id so exports of the
+/// same content are identical.
+async fn export(path: &Path) -> Result<()> {
+    let db = db::connect(&env::var("DATABASE_URL")?).await?;
+
+    let mut statuses = db.list_statuses().await?;
+    statuses.sort_by_key(|status| status.id);
+    let mut help_messages = db.list_help_messages().await?;
+    help_messages.sort_by_key(|help_message| help_message.id);
+    let mut leet = db.list_leet().await?;
+    leet.sort_by_key(|row| row.id);
+    let mut ai_reactions = db.list_reactions(None).await?;
+    ai_reactions.sort_by_key(|reaction| reaction.id);
+    let mut ai_command_aliases = db.list_aliases().await?;
+    ai_command_aliases.sort_by_key(|alias| alias.id);
+    let mut prefixes = db.list_prefixes().await?;
+    prefixes.sort_by_key(|prefix| prefix.id);
+
+    let content = BotContent {
+        statuses: Some(statuses),
+        help_messages: Some(help_messages),
+        leet: Some(leet),
+        ai_reactions: Some(ai_reactions),
+        ai_command_aliases: Some(ai_command_aliases),
+        prefixes: Some(prefixes),
+    };
+
+    let json = serde_json::to_string_pretty(&content)?;
+    fs::write(path, json + "\n").map_err(|e| anyhow!("Failed to write {}: {e}", path.display()))?;
+
+    info!("Exported the content to {}", path.display());
+
+    Ok(())
+}
+
+/// Replaces the content with the one in the JSON file. Sections missing from
+/// the file are left untouched.
+async fn import(path: &Path) -> Result<()> {
+    let json =
+        fs::read_to_string(path).map_err(|e| anyhow!("Failed to read {}: {e}", path.display()))?;
+    let content: BotContent = serde_json::from_str(&json)
+        .map_err(|e| anyhow!("{} isn't a valid export: {e}", path.display()))?;
+
+    let db = db::connect(&env::var("DATABASE_URL")?).await?;
+    db.replace_content(&content).await?;
+
+    let sections = [
+        ("statuses", content.statuses.as_ref().map(Vec::len)),
+        (
+            "help messages",
+            content.help_messages.as_ref().map(Vec::len),
+        ),
+        ("leet translations", content.leet.as_ref().map(Vec::len)),
+        ("AI reactions", content.ai_reactions.as_ref().map(Vec::len)),
+        (
+            "AI command aliases",
+            content.ai_command_aliases.as_ref().map(Vec::len),
+        ),
+        ("prefixes", content.prefixes.as_ref().map(Vec::len)),
+    ]
+    .iter()
+    .filter_map(|(name, count)| count.map(|count| format!("{count} {name}")))
+    .collect::<Vec<_>>();
+
+    if sections.is_empty() {
+        warn!(
+            "{} contains no content, nothing was imported",
+            path.display()
+        );
+    } else {
+        info!(
+            "Imported {}, restart the bot to use them",
+            sections.join(", ")
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,338 @@
+use std::{cmp::Reverse, time::Instant};
+
+use anyhow::Result;
+use chrono::Utc;
+use serenity::builder::CreateEmbed;
+
+use crate::{
+    apis::reddit::{
+        is_recently_refreshed, is_valid_subreddit, normalize_subreddit, refresh_subreddit,
+    },
+    db::{models::RedditPost, repositories::ReactionRepository},
+    helpers::{
+        args::{ArgKind, ArgSpec},
+        cooldowns::{Cooldown, CooldownCheck},
+        types::{CommandData, LeetMap},
+        utils::{random_element_vec, split_message},
+    },
+};
+
+/// The arguments of reaction messages such as `$hug @user`. Reactions are
+/// part of normal chat, so anything after the user is accepted and ignored.
+pub const REACTION_ARGS: &[ArgSpec] = &[
+    ArgSpec::optional("user", ArgKind::User),
+    ArgSpec::optional("message", ArgKind::Rest),
+];
+
+/// How often a user can use reactions. Only messages of existing reaction
+/// commands count, and as reactions are part of normal chat, messages over
+/// the limit are ignored without a reply. Bot owners aren't limited.
+pub const REACTION_COOLDOWN: Cooldown = Cooldown::per_user(5, 30);
+
+/// Discord's limit for the length of a single message.
+const MESSAGE_LIMIT: usize = 2000;
+/// How many messages a single translation may be split into before the rest
+/// is cut off, so one command can't flood a channel.
+const MAX_MESSAGES: usize = 3;
+
+/// Translates the text into leet speak, picking a random translation for
+/// every character that has more than one.
+fn to_leet(text: &str, leet: &LeetMap) -> String {
+    text.chars()
+        .map(|c| {
+            leet.get(&c.to_ascii_lowercase())
+                .and_then(|translations| random_element_vec(translations))
+                .unwrap_or_else(|| c.to_string())
+        })
+        .collect()
+}
+
+/// Translates leet speak back into plain text by always replacing the
+/// longest matching translation first.
+fn from_leet(text: &str, leet: &LeetMap) -> String {
+    let mut translations = leet
+        .iter()
+        .flat_map(|(source, translated)| translated.iter().map(move |t| (t.as_str(), *source)))
+        .filter(|(translated, _)| !translated.is_empty())
+        .collect::<Vec<_>>();
+    translations.sort_by_key(|(translated, _)| Reverse(translated.len()));
+
+    let mut result = String::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        match translations
+            .iter()
+            .find(|(translated, _)| rest.starts_with(translated))
+        {
+            Some((translated, source)) => {
+                result.push(*source);
+                rest = &rest[translated.len()..];
+            }
+            None => {
+                result.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    result
+}
+
+/// Sends a translation, split into several messages if it is too long for a
+/// single one. Anything beyond [`MAX_MESSAGES`] messages is cut off. The text
+/// comes from the author, so mentions in it don't ping.
+async fn send_translation(data: &CommandData<'_>, text: &str) -> Result<()> {
+    let mut chunks = split_message(text, MESSAGE_LIMIT);
+
+    if chunks.len() > MAX_MESSAGES {
+        chunks.truncate(MAX_MESSAGES);
+        if let Some(last) = chunks.last_mut() {
+            let keep = last.chars().count().min(MESSAGE_LIMIT - 1);
+            *last = last.chars().take(keep).collect::<String>() + "…";
+        }
+    }
+
+    for chunk in chunks {
+        data.reply_without_mentions(chunk).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn leet(data: CommandData<'_>) -> Result<()> {
+    let text = data.args.text("text").unwrap_or_default();
+    send_translation(&data, &to_leet(text, &data.handler.leet)).await
+}
+
+pub async fn unleet(data: CommandData<'_>) -> Result<()> {
+    let text = data.args.text("text").unwrap_or_default();
+    send_translation(&data, &from_leet(text, &data.handler.leet)).await
+}
+
+/// Conjugates a verb for the third person, e.g. `hug` -> `hugs`,
+/// `kiss` -> `kisses`.
+fn third_person(verb: &str) -> String {
+    if ["s", "sh", "ch", "x", "z", "o"]
+        .iter()
+        .any(|ending| verb.ends_with(ending))
+    {
+        format!("{verb}es")
+    } else {
+        format!("{verb}s")
+    }
+}
+
+/// Picks a random reaction of the reaction command, resolving aliases through
+/// the `ai_command_aliases` table first.
+///
+/// # Returns
+/// The resolved command and the reaction, or `None` if the command has no
+/// reactions.
+async fn pick_reaction(
+    db: &dyn ReactionRepository,
+    react_cmd: &str,
+) -> Result<Option<(String, String)>> {
+    let command = db
+        .resolve_alias(react_cmd)
+        .await?
+        .unwrap_or_else(|| react_cmd.to_string());
+
+    let reactions = db
+        .list_reactions(Some(&command))
+        .await?
+        .into_iter()
+        .map(|row| row.reaction)
+        .collect::<Vec<_>>();
+
+    Ok(random_element_vec(&reactions).map(|reaction| (command, reaction)))
+}
+
+/// Responds to reaction messages such as `$hug @user` with a random reaction
+/// for the command. Messages starting with `$` that don't belong to any
+/// reaction command are ignored.
+pub async fn react(data: CommandData<'_>) -> Result<()> {
+    let Some(react_cmd) = data.react_cmd.as_deref() else {
+        return Ok(());
+    };
+
+    let Some((command, reaction)) = pick_reaction(&*data.handler.db, react_cmd).await? else {
+        return Ok(());
+    };
+
+    let author = data.author().id;
+    if !data.handler.config().bot_owners.contains(author) {
+        let check =
+            data.handler
+                .cooldowns
+                .hit("react", &REACTION_COOLDOWN, author.get(), Instant::now());
+        if matches!(check, CooldownCheck::Limited { .. }) {
+            return Ok(());
+        }
+    }
+
+    let target = match data.args.user("user") {
+        Some(target) if target == author => "themselves".to_string(),
+        Some(target) => format!("<@{target}>"),
+        None => "everyone".to_string(),
+    };
+    let description = format!("<@{author}> {} {target}", third_person(&command));
+
+    let embed = CreateEmbed::default().color(data.handler.config().embed_colour);
+    let embed = if reaction.starts_with("http") {
+        embed.description(description).image(reaction)
+    } else {
+        embed.description(format!("{description}\n\n{reaction}"))
+    };
+
+    data.reply_embed(embed).await?;
+
+    Ok(())
+}
+
+/// Picks a random cached image post from the subreddit. NSFW posts are only
+/// picked if they are allowed here or the subreddit has nothing else, in
+/// which case posting them is refused later on.
+async fn random_cached_post(data: &CommandData<'_>, subreddit: &str) -> Result<Option<RedditPost>> {
+    data.handler
+        .db
+        .random_reddit_post(subreddit, data.allow_nsfw)
+        .await
+}
+
+/// Posts a random hot image from the subreddit. Posts are served from the
+/// `reddit_posts` table, which is filled the first time a subreddit is
+/// requested and kept fresh by the background refresh loop for as long as it
+/// keeps being requested.
+pub async fn reddit(data: CommandData<'_>) -> Result<()> {
+    let subreddit = normalize_subreddit(data.args.text("subreddit").unwrap_or_default());
+    let subreddit = subreddit.as_str();
+
+    if !is_valid_subreddit(subreddit) {
+        data.reply(format!("`{subreddit}` is not a valid subreddit name"))
+            .await?;
+        return Ok(());
+    }
+
+    data.handler
+        .db
+        .touch_subreddit(subreddit, Utc::now().timestamp())
+        .await?;
+
+    let mut post = random_cached_post(&data, subreddit).await?;
+
+    if post.is_none() && !is_recently_refreshed(data.handler, subreddit).await? {
+        refresh_subreddit(data.handler, subreddit).await?;
+        post = random_cached_post(&data, subreddit).await?;
+    }
+
+    let Some(post) = post else {
+        data.reply(format!("Couldn't find any images on r/{subreddit}"))
+            .await?;
+        return Ok(());
+    };
+
+    let embed = CreateEmbed::default()
+        .title(post.title)
+        .url(post.permalink)
+        .image(post.url)
+        .color(data.handler.config().embed_colour);
+
+    data.reply_content(embed, post.over_18).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory::MemoryDatabase;
+
+    fn leet_map() -> LeetMap {
+        [
+            ('a', vec!["4", "/-\\"]),
+            ('e', vec!["3"]),
+            ('h', vec!["|-|"]),
+            ('i', vec!["|"]),
+            ('l', vec!["1"]),
+            ('m', vec!["|\\/|"]),
+            ('o', vec!["0"]),
+            ('v', vec!["\\/"]),
+        ]
+        .into_iter()
+        .map(|(c, translations)| (c, translations.into_iter().map(String::from).collect()))
+        .collect()
+    }
+
+    #[test]
+    fn translates_into_leet() {
+        let leet = leet_map();
+
+        assert_eq!(to_leet("Hello", &leet), "|-|3110");
+        assert_eq!(to_leet("xyz", &leet), "xyz");
+        assert!(["4", "/-\\"].contains(&to_leet("a", &leet).as_str()));
+    }
+
+    #[test]
+    fn translates_from_leet_longest_match_first() {
+        let leet = leet_map();
+
+        assert_eq!(from_leet("|\\/|", &leet), "m");
+        assert_eq!(from_leet("|-|1", &leet), "hl");
+        assert_eq!(from_leet("/-\\|\\/4", &leet), "aiva");
+    }
+
+    #[test]
+    fn round_trips_non_ascii_text() {
+        let leet = leet_map();
+        let text = "héllo wörld, ça va? ✨ ハロー";
+
+        assert_eq!(from_leet(&to_leet(text, &leet), &leet), text);
+    }
+
+    #[tokio::test]
+    async fn picks_a_reaction_of_the_command() {
+        let db = MemoryDatabase::default();
+        db.add_reaction("hug", "https://example.com/hug.gif")
+            .await
+            .unwrap();
+        db.add_reaction("pat", "https://example.com/pat.gif")
+            .await
+            .unwrap();
+
+        let picked = pick_reaction(&db, "hug").await.unwrap();
+
+        assert_eq!(
+            picked,
+            Some(("hug".to_string(), "https://example.com/hug.gif".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn resolves_aliases_before_picking() {
+        let db = MemoryDatabase::default();
+        db.add_reaction("hug", "*hugs*").await.unwrap();
+        db.set_alias("cuddle", "hug").await.unwrap();
+
+        let picked = pick_reaction(&db, "cuddle").await.unwrap();
+
+        assert_eq!(picked, Some(("hug".to_string(), "*hugs*".to_string())));
+    }
+
+    #[tokio::test]
+    async fn ignores_commands_without_reactions() {
+        let db = MemoryDatabase::default();
+        db.add_reaction("hug", "*hugs*").await.unwrap();
+        db.set_alias("cuddle", "snuggle").await.unwrap();
+
+        assert_eq!(pick_reaction(&db, "5").await.unwrap(), None);
+        assert_eq!(pick_reaction(&db, "cuddle").await.unwrap(), None);
+    }
+
+    #[test]
+    fn conjugates_reaction_verbs() {
+        assert_eq!(third_person("hug"), "hugs");
+        assert_eq!(third_person("kiss"), "kisses");
+        assert_eq!(third_person("punch"), "punches");
+    }
+}
@@ -1,21 +1,326 @@
+use std::collections::HashMap;
+
 use anyhow::{anyhow, Result};
-use serenity::{all::CreateMessage, builder::CreateEmbed};
+use serenity::builder::CreateEmbed;
+
+use super::{find_command, Category, Command, COMMANDS};
+use crate::{
+    apis::{
+        exchange::{parse_amount, parse_currency},
+        imgur::{is_image_type, MAX_IMAGE_SIZE},
+    },
+    helpers::{
+        paginate::{chunk_lines, paginate},
+        types::CommandData,
+        utils::parse_target_user,
+    },
+};
 
-use crate::helpers::{types::MessageCommandData, utils::parse_target_user};
+const CURRENCIES_PER_PAGE: usize = 20;
+/// How many images a single `imgur` command may upload.
+const MAX_UPLOADS: usize = 5;
 
-pub async fn user_avatar(data: MessageCommandData<'_>) -> Result<()> {
-    let user = parse_target_user(&data, 1).await?;
+pub async fn user_avatar(data: CommandData<'_>) -> Result<()> {
+    let user = parse_target_user(&data, "user").await?;
 
     let embed = CreateEmbed::default()
         .title(format!("{}'s avatar", user.name))
         .image(user.face())
-        .color(data.handler.config.embed_colour);
+        .color(data.handler.config().embed_colour);
 
-    data.msg
-        .channel_id
-        .send_message(&data.ctx, CreateMessage::default().add_embed(embed))
+    data.reply_embed(embed)
         .await
         .map_err(|_| anyhow!("Failed to send message"))?;
 
     Ok(())
 }
+
+/// Converts an amount from one currency to another. Currencies can be given
+/// as codes or common symbols, and the amount may carry the symbol of the
+/// source currency, in which case the target is the only other argument,
+/// e.g. `convert $20 eur`.
+pub async fn convert(data: CommandData<'_>) -> Result<()> {
+    let usage = format!("Usage: `{}convert <amount> <from> <to>`", data.prefix);
+
+    let Some((amount, symbol_currency)) =
+        parse_amount(data.args.text("amount").unwrap_or_default())
+    else {
+        data.reply(format!("That's not a valid amount\n{usage}"))
+            .await?;
+        return Ok(());
+    };
+
+    let first = data.args.text("from").unwrap_or_default();
+    let (from, to) = match (symbol_currency, data.args.text("to")) {
+        (_, Some(to)) => (parse_currency(first), parse_currency(to)),
+        (Some(from), None) => (Some(from), parse_currency(first)),
+        (None, None) => {
+            data.reply(usage).await?;
+            return Ok(());
+        }
+    };
+
+    let (Some(from), Some(to)) = (from, to) else {
+        data.reply(format!(
+            "Unknown currency, use `{}currencies` to see all supported ones",
+            data.prefix
+        ))
+        .await?;
+        return Ok(());
+    };
+
+    let rate = data
+        .handler
+        .exchange
+        .rate(&data.handler.config(), data.handler.db.as_ref(), &from, &to)
+        .await?;
+
+    data.reply(format!("{amount:.2} {from} = {:.2} {to}", amount * rate))
+        .await?;
+
+    Ok(())
+}
+
+/// Lists all currencies supported by the `convert` command.
+pub async fn currencies(data: CommandData<'_>) -> Result<()> {
+    let lines = data
+        .handler
+        .exchange
+        .currencies(&data.handler.config(), data.handler.db.as_ref())
+        .await?
+        .into_iter()
+        .map(|(code, name)| format!("`{code}` - {name}"))
+        .collect::<Vec<_>>();
+
+    let pages = chunk_lines(&lines, CURRENCIES_PER_PAGE)
+        .into_iter()
+        .map(|description| {
+            CreateEmbed::default()
+                .title("Supported currencies")
+                .description(description)
+                .color(data.handler.config().embed_colour)
+        })
+        .collect();
+
+    paginate(&data, pages).await
+}
+
+/// Checks if an attachment can be uploaded to Imgur by its type and size.
+///
+/// # Returns
+/// Why the attachment can't be uploaded, or `None` if it can.
+fn attachment_problem(filename: &str, content_type: Option<&str>, size: u64) -> Option<String> {
+    if !content_type.is_some_and(is_image_type) {
+        return Some(format!("`{filename}` is not a supported image"));
+    }
+    if size > MAX_IMAGE_SIZE {
+        return Some(format!("`{filename}` is larger than 20MB"));
+    }
+    None
+}
+
+/// Uploads the images attached to the message and the linked image to Imgur
+/// and replies with their permanent links. Attachments are checked for their
+/// size and type first. Links are only checked to be http(s) links, as the
+/// bot never fetches them. Imgur does that and validates the type and size of
+/// what it finds, rejecting anything that isn't a supported image. If some
+/// uploads fail, the links of the others are still sent.
+pub async fn imgur(data: CommandData<'_>) -> Result<()> {
+    let mut urls = Vec::new();
+
+    for attachment in data.source.attachments() {
+        let problem = attachment_problem(
+            &attachment.filename,
+            attachment.content_type.as_deref(),
+            u64::from(attachment.size),
+        );
+
+        if let Some(problem) = problem {
+            data.reply(problem).await?;
+            return Ok(());
+        }
+
+        urls.push(attachment.url.clone());
+    }
+
+    if let Some(url) = data.args.text("url") {
+        let url = url.trim_start_matches('<').trim_end_matches('>');
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            data.reply("That's not a valid link").await?;
+            return Ok(());
+        }
+        urls.push(url.to_string());
+    }
+
+    if urls.is_empty() {
+        data.reply(format!(
+            "Attach or link an image to upload\nUsage: `{}imgur [url]`",
+            data.prefix
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    if urls.len() > MAX_UPLOADS {
+        data.reply(format!(
+            "You can only upload up to {MAX_UPLOADS} images at once"
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    let imgur = &data.handler.imgur;
+    let config = data.handler.config();
+    let mut lines = Vec::new();
+
+    for url in &urls {
+        match imgur.upload_url(&config, url).await {
+            Ok(link) => lines.push(link),
+            Err(e) => lines.push(format!("Couldn't upload <{url}>: {e}")),
+        }
+    }
+
+    data.reply(lines.join("\n")).await?;
+
+    Ok(())
+}
+
+pub async fn test(data: CommandData<'_>) -> Result<()> {
+    data.reply("Test command").await?;
+
+    Ok(())
+}
+
+/// Shows an overview of all commands grouped by category, or the usage,
+/// aliases, examples, required permissions and cooldown of a single command.
+///
+/// Descriptions can be overridden through the `help_messages` table without
+/// touching the command definitions.
+pub async fn help(data: CommandData<'_>) -> Result<()> {
+    let overrides = data
+        .handler
+        .db
+        .list_help_messages()
+        .await?
+        .into_iter()
+        .map(|help| (help.cmd, help.desc))
+        .collect::<HashMap<_, _>>();
+
+    let description = |cmd: &Command| {
+        overrides
+            .get(cmd.name)
+            .map_or(cmd.description.to_string(), ToString::to_string)
+    };
+
+    let config = data.handler.config();
+    let is_owner = config.bot_owners.contains(data.author().id);
+    let is_visible = |cmd: &Command| (is_owner || !cmd.owner_only) && cmd.is_enabled(&config);
+    let prefix = &data.prefix;
+
+    if let Some(name) = data.args.text("command") {
+        let Some(cmd) = find_command(&name.to_lowercase()).filter(|cmd| is_visible(cmd)) else {
+            data.reply(format!("No command called `{name}` found"))
+                .await?;
+            return Ok(());
+        };
+
+        let mut embed = CreateEmbed::default()
+            .title(format!("{prefix}{}", cmd.name))
+            .description(description(cmd))
+            .color(config.embed_colour)
+            .field("Usage", format!("`{}`", cmd.usage(prefix)), false);
+
+        if !cmd.aliases.is_empty() {
+            let aliases = cmd
+                .aliases
+                .iter()
+                .map(|alias| format!("`{prefix}{alias}`"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            embed = embed.field("Aliases", aliases, false);
+        }
+
+        if !cmd.examples.is_empty() {
+            let examples = cmd
+                .examples
+                .iter()
+                .map(|example| format!("`{prefix}{example}`"))
+                .collect::<Vec<_>>()
+                .join("\n");
+            embed = embed.field("Examples", examples, false);
+        }
+
+        if cmd.owner_only {
+            embed = embed.field("Required permissions", "Bot owner", false);
+        } else if !cmd.permissions.is_empty() {
+            embed = embed.field(
+                "Required permissions",
+                cmd.permissions.get_permission_names().join(", "),
+                false,
+            );
+        }
+
+        if let Some(cooldown) = &cmd.cooldown {
+            embed = embed.field("Cooldown", cooldown.to_string(), false);
+        }
+
+        return paginate(&data, vec![embed]).await;
+    }
+
+    let pages = Category::ALL
+        .iter()
+        .filter_map(|category| {
+            let lines = COMMANDS
+                .iter()
+                .filter(|cmd| cmd.category == *category && is_visible(cmd))
+                .map(|cmd| format!("`{prefix}{}` - {}", cmd.name, description(cmd)))
+                .collect::<Vec<_>>();
+
+            if lines.is_empty() {
+                return None;
+            }
+
+            Some(
+                CreateEmbed::default()
+                    .title(format!("{} commands", category.name()))
+                    .description(format!(
+                        "Use `{prefix}help <command>` for more details on a command\n\n{}",
+                        lines.join("\n")
+                    ))
+                    .color(config.embed_colour),
+            )
+        })
+        .collect();
+
+    paginate(&data, pages).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_images_within_the_size_limit() {
+        assert_eq!(attachment_problem("a.png", Some("image/png"), 1024), None);
+        assert_eq!(
+            attachment_problem("a.gif", Some("image/gif"), MAX_IMAGE_SIZE),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_other_types_and_large_images() {
+        assert_eq!(
+            attachment_problem("a.txt", Some("text/plain"), 10).as_deref(),
+            Some("`a.txt` is not a supported image")
+        );
+        assert_eq!(
+            attachment_problem("a", None, 10).as_deref(),
+            Some("`a` is not a supported image")
+        );
+        assert_eq!(
+            attachment_problem("a.png", Some("image/png"), MAX_IMAGE_SIZE + 1).as_deref(),
+            Some("`a.png` is larger than 20MB")
+        );
+    }
+}
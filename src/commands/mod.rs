@@ -1 +1,719 @@
+pub mod fun;
 pub mod misc;
+pub mod owner;
+pub mod settings;
+
+use std::time::Instant;
+
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+use serenity::{
+    all::{CreateCommand, GuildId, Permissions, ResolvedOption},
+    prelude::Context,
+};
+
+use crate::{
+    config::{Config, Integration},
+    helpers::{
+        args::{parse_args, parse_options, signature, ArgKind, ArgSpec, Args},
+        cooldowns::{Cooldown, CooldownBucket, CooldownCheck},
+        types::{CommandData, CommandSource, Handler},
+        utils::{format_duration, nsfw_enabled},
+    },
+};
+
+/// The signature every command handler has to implement.
+///
+/// Async functions can't be stored as plain function pointers, so each
+/// registry entry wraps its handler in a closure that boxes the future.
+pub type CommandFn = for<'a> fn(CommandData<'a>) -> BoxFuture<'a, Result<()>>;
+
+/// The category a command belongs to, used to group commands together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Misc,
+    Fun,
+    Settings,
+    Owner,
+}
+
+impl Category {
+    /// All categories, in the order they are shown in the help command.
+    pub const ALL: [Category; 4] = [
+        Category::Misc,
+        Category::Fun,
+        Category::Settings,
+        Category::Owner,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Category::Misc => "Misc",
+            Category::Fun => "Fun",
+            Category::Settings => "Settings",
+            Category::Owner => "Owner",
+        }
+    }
+}
+
+/// A single entry in the command registry.
+///
+/// Everything that needs to know about commands (dispatch, help, permissions,
+/// cooldowns, ...) should be derived from these entries instead of matching
+/// on command names directly.
+pub struct Command {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub category: Category,
+    pub description: &'static str,
+    pub args: &'static [ArgSpec],
+    /// The subcommands chosen by the `action` argument, whose own arguments
+    /// are parsed from the `query` argument.
+    pub subcommands: &'static [Subcommand],
+    /// Example invocations without the prefix, shown in the help command.
+    pub examples: &'static [&'static str],
+    /// Whether only the bot owners are allowed to use this command.
+    pub owner_only: bool,
+    /// The guild permissions a member needs to use this command.
+    /// Commands that require any permissions can't be used in DMs.
+    pub permissions: Permissions,
+    /// Whether this command is also registered as a slash command.
+    pub slash: bool,
+    /// Whether this command posts external content that can be marked NSFW.
+    pub nsfw_content: bool,
+    /// The integration this command relies on. The command is disabled and
+    /// hidden from help if the integration isn't configured.
+    pub integration: Option<Integration>,
+    /// How often the command can be used. Bot owners aren't limited.
+    pub cooldown: Option<Cooldown>,
+    pub run: CommandFn,
+}
+
+/// A subcommand such as `status remove`, declaring the arguments that
+/// follow its name.
+pub struct Subcommand {
+    pub name: &'static str,
+    pub args: &'static [ArgSpec],
+}
+
+impl Command {
+    /// Checks if the given name is either the name or one of the aliases of
+    /// this command.
+    pub fn matches(&self, name: &str) -> bool {
+        self.name == name || self.aliases.contains(&name)
+    }
+
+    /// Checks if the integration this command relies on, if any, is
+    /// configured.
+    pub fn is_enabled(&self, config: &Config) -> bool {
+        self.integration
+            .is_none_or(|integration| config.is_enabled(integration))
+    }
+
+    /// Returns the usage signature of this command with the given prefix,
+    /// e.g. `h!avatar [user]`.
+    pub fn usage(&self, prefix: &str) -> String {
+        signature(prefix, self.name, self.args)
+    }
+
+    /// Parses the arguments of a message command, including those of the
+    /// chosen subcommand.
+    ///
+    /// # Errors
+    /// * If the arguments are invalid. The error ends with the usage of the
+    ///   command, or of the subcommand if one was chosen.
+    pub fn parse_message_args(&self, input: &str, prefix: &str) -> Result<Args> {
+        let args = parse_args(input, self.args)
+            .map_err(|e| anyhow!("{e}\nUsage: `{}`", self.usage(prefix)))?;
+
+        self.parse_subcommand_args(args, prefix)
+    }
+
+    /// Parses the options of a slash command, including the arguments of the
+    /// chosen subcommand.
+    ///
+    /// # Errors
+    /// * If the options are invalid. The error ends with the usage of the
+    ///   command, or of the subcommand if one was chosen.
+    pub fn parse_slash_args(&self, options: &[ResolvedOption<'_>]) -> Result<Args> {
+        let args = parse_options(options, self.args)
+            .map_err(|e| anyhow!("{e}\nUsage: `{}`", self.usage("/")))?;
+
+        self.parse_subcommand_args(args, "/")
+    }
+
+    fn parse_subcommand_args(&self, args: Args, prefix: &str) -> Result<Args> {
+        let subcommand = args.text("action").and_then(|action| {
+            self.subcommands
+                .iter()
+                .find(|subcommand| subcommand.name.eq_ignore_ascii_case(action))
+        });
+        let Some(subcommand) = subcommand else {
+            return Ok(args);
+        };
+
+        let name = format!("{} {}", self.name, subcommand.name);
+        let subcommand_args = parse_args(args.text("query").unwrap_or_default(), subcommand.args)
+            .map_err(|e| {
+            anyhow!(
+                "{e}\nUsage: `{}`",
+                signature(prefix, &name, subcommand.args)
+            )
+        })?;
+
+        Ok(args.merge(subcommand_args))
+    }
+
+    /// Builds the slash command registration for this command from its
+    /// name, description and arguments.
+    pub fn slash_command(&self) -> CreateCommand {
+        let mut command = CreateCommand::new(self.name)
+            .description(self.description)
+            .set_options(self.args.iter().map(ArgSpec::slash_option).collect());
+
+        if !self.permissions.is_empty() {
+            command = command
+                .default_member_permissions(self.permissions)
+                .dm_permission(false);
+        }
+
+        command
+    }
+
+    /// Checks if the author is allowed to use this command. Disabled
+    /// commands tell the author so, owner-only
+    /// commands are silently ignored for message commands, everything else
+    /// tells the author why they can't use the command.
+    ///
+    /// # Errors
+    /// * If fetching the author's permissions or sending the reply fails.
+    pub async fn check_access(
+        &self,
+        ctx: &Context,
+        handler: &Handler,
+        source: CommandSource<'_>,
+    ) -> Result<bool> {
+        if !self.is_enabled(&handler.config()) {
+            source
+                .reply(ctx, "This command is disabled because it isn't configured")
+                .await?;
+            return Ok(false);
+        }
+
+        if self.owner_only && !handler.config().bot_owners.contains(source.author().id) {
+            if let CommandSource::Interaction(_) = source {
+                source
+                    .reply(ctx, "This command can only be used by the bot owners")
+                    .await?;
+            }
+            return Ok(false);
+        }
+
+        if !self.permissions.is_empty() {
+            let has_permissions = source
+                .member_permissions(ctx)
+                .await?
+                .is_some_and(|permissions| permissions.contains(self.permissions));
+
+            if !has_permissions {
+                let missing = self.permissions.get_permission_names().join(", ");
+                source
+                    .reply(
+                        ctx,
+                        format!(
+                            "You need the following permissions to use this command: {missing}"
+                        ),
+                    )
+                    .await?;
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Records a use of this command and checks if it is on cooldown. Bot
+    /// owners bypass cooldowns. Message commands only tell the author once
+    /// per cooldown when they can use the command again, so spamming the
+    /// command doesn't make the bot spam replies. Interactions are always
+    /// answered, as they were deferred already.
+    ///
+    /// # Errors
+    /// * If sending the reply fails.
+    pub async fn check_cooldown(
+        &self,
+        ctx: &Context,
+        handler: &Handler,
+        source: CommandSource<'_>,
+    ) -> Result<bool> {
+        let Some(cooldown) = &self.cooldown else {
+            return Ok(true);
+        };
+
+        if handler.config().bot_owners.contains(source.author().id) {
+            return Ok(true);
+        }
+
+        let id = match cooldown.bucket {
+            CooldownBucket::User => source.author().id.get(),
+            CooldownBucket::Channel => source.channel_id().get(),
+            CooldownBucket::Guild => source
+                .guild_id()
+                .map_or(source.channel_id().get(), GuildId::get),
+        };
+
+        match handler
+            .cooldowns
+            .hit(self.name, cooldown, id, Instant::now())
+        {
+            CooldownCheck::Ready => Ok(true),
+            CooldownCheck::Limited {
+                retry_after,
+                notify,
+            } => {
+                if notify || matches!(source, CommandSource::Interaction(_)) {
+                    // Round up, so it never says "again in 0s".
+                    let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+                    source
+                        .reply(
+                            ctx,
+                            format!(
+                                "Slow down! You can use this command again in {}",
+                                format_duration(i64::try_from(seconds).unwrap_or(i64::MAX))
+                            ),
+                        )
+                        .await?;
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    /// Decides if NSFW content may be posted in response to this command.
+    /// That's only the case for commands that post such content at all, and
+    /// only in age-restricted channels of guilds that haven't disabled it.
+    ///
+    /// # Errors
+    /// * If fetching the channel or the guild settings fails.
+    pub async fn allows_nsfw(
+        &self,
+        ctx: &Context,
+        handler: &Handler,
+        source: CommandSource<'_>,
+    ) -> Result<bool> {
+        if !self.nsfw_content {
+            return Ok(false);
+        }
+
+        let Some(guild_id) = source.guild_id() else {
+            return Ok(false);
+        };
+
+        Ok(source.is_nsfw_channel(ctx).await? && nsfw_enabled(guild_id, handler).await?)
+    }
+}
+
+/// All commands the bot knows about.
+pub static COMMANDS: &[Command] = &[
+    Command {
+        name: "avatar",
+        aliases: &["pfp"],
+        category: Category::Misc,
+        description: "Shows the avatar of a user",
+        args: &[ArgSpec::optional("user", ArgKind::User)],
+        subcommands: &[],
+        examples: &["avatar", "avatar @user"],
+        owner_only: false,
+        permissions: Permissions::empty(),
+        slash: true,
+        nsfw_content: false,
+        integration: None,
+        cooldown: Some(Cooldown::per_user(3, 10)),
+        run: |data| Box::pin(misc::user_avatar(data)),
+    },
+    Command {
+        name: "help",
+        aliases: &["commands"],
+        category: Category::Misc,
+        description: "Shows all commands or the details of a single command",
+        args: &[ArgSpec::optional("command", ArgKind::Text)],
+        subcommands: &[],
+        examples: &["help", "help avatar"],
+        owner_only: false,
+        permissions: Permissions::empty(),
+        slash: true,
+        nsfw_content: false,
+        integration: None,
+        cooldown: Some(Cooldown::per_user(2, 10)),
+        run: |data| Box::pin(misc::help(data)),
+    },
+    Command {
+        name: "convert",
+        aliases: &["currency"],
+        category: Category::Misc,
+        description: "Converts an amount of money from one currency to another",
+        args: &[
+            ArgSpec::required("amount", ArgKind::Text),
+            ArgSpec::required("from", ArgKind::Text),
+            ArgSpec::optional("to", ArgKind::Text),
+        ],
+        subcommands: &[],
+        examples: &[
+            "convert 20 usd eur",
+            "convert 15.50 € £",
+            "convert $100 jpy",
+        ],
+        owner_only: false,
+        permissions: Permissions::empty(),
+        slash: true,
+        nsfw_content: false,
+        integration: Some(Integration::Exchange),
+        cooldown: Some(Cooldown::per_user(5, 30)),
+        run: |data| Box::pin(misc::convert(data)),
+    },
+    Command {
+        name: "currencies",
+        aliases: &[],
+        category: Category::Misc,
+        description: "Lists all currencies that can be converted",
+        args: &[],
+        subcommands: &[],
+        examples: &[],
+        owner_only: false,
+        permissions: Permissions::empty(),
+        slash: true,
+        nsfw_content: false,
+        integration: Some(Integration::Exchange),
+        cooldown: Some(Cooldown::per_user(2, 30)),
+        run: |data| Box::pin(misc::currencies(data)),
+    },
+    Command {
+        name: "imgur",
+        aliases: &[],
+        category: Category::Misc,
+        description: "Uploads the attached or linked images to Imgur",
+        args: &[ArgSpec::optional("url", ArgKind::Text)],
+        subcommands: &[],
+        examples: &[
+            "imgur https://example.com/image.png",
+            "imgur (with an attached image)",
+        ],
+        owner_only: false,
+        permissions: Permissions::empty(),
+        slash: true,
+        nsfw_content: false,
+        integration: Some(Integration::Imgur),
+        cooldown: Some(Cooldown::per_user(1, 30)),
+        run: |data| Box::pin(misc::imgur(data)),
+    },
+    Command {
+        name: "test",
+        aliases: &[],
+        category: Category::Misc,
+        description: "Test command",
+        args: &[],
+        subcommands: &[],
+        examples: &[],
+        owner_only: false,
+        permissions: Permissions::empty(),
+        slash: false,
+        nsfw_content: false,
+        integration: None,
+        cooldown: None,
+        run: |data| Box::pin(misc::test(data)),
+    },
+    Command {
+        name: "leet",
+        aliases: &["1337"],
+        category: Category::Fun,
+        description: "Translates text into leet speak",
+        args: &[ArgSpec::required("text", ArgKind::Rest)],
+        subcommands: &[],
+        examples: &["leet hello there"],
+        owner_only: false,
+        permissions: Permissions::empty(),
+        slash: true,
+        nsfw_content: false,
+        integration: None,
+        cooldown: Some(Cooldown::per_user(5, 10)),
+        run: |data| Box::pin(fun::leet(data)),
+    },
+    Command {
+        name: "unleet",
+        aliases: &[],
+        category: Category::Fun,
+        description: "Translates leet speak back into plain text",
+        args: &[ArgSpec::required("text", ArgKind::Rest)],
+        subcommands: &[],
+        examples: &["unleet h3ll0 7h3r3"],
+        owner_only: false,
+        permissions: Permissions::empty(),
+        slash: true,
+        nsfw_content: false,
+        integration: None,
+        cooldown: Some(Cooldown::per_user(5, 10)),
+        run: |data| Box::pin(fun::unleet(data)),
+    },
+    Command {
+        name: "reddit",
+        aliases: &["r"],
+        category: Category::Fun,
+        description: "Posts a random hot image from a subreddit",
+        args: &[ArgSpec::required("subreddit", ArgKind::Text)],
+        subcommands: &[],
+        examples: &["reddit aww", "reddit r/EarthPorn"],
+        owner_only: false,
+        permissions: Permissions::empty(),
+        slash: true,
+        nsfw_content: true,
+        integration: Some(Integration::Reddit),
+        cooldown: Some(Cooldown::per_channel(5, 15)),
+        run: |data| Box::pin(fun::reddit(data)),
+    },
+    Command {
+        name: "prefix",
+        aliases: &[],
+        category: Category::Settings,
+        description: "Shows and manages the prefixes of this server",
+        args: &[
+            ArgSpec::required("action", ArgKind::Text),
+            ArgSpec::optional("prefix", ArgKind::Text),
+        ],
+        subcommands: &[],
+        examples: &["prefix show", "prefix set h?", "prefix add !"],
+        owner_only: false,
+        permissions: Permissions::MANAGE_GUILD,
+        slash: false,
+        nsfw_content: false,
+        integration: None,
+        cooldown: Some(Cooldown::per_guild(3, 10)),
+        run: |data| Box::pin(settings::prefix(data)),
+    },
+    Command {
+        name: "nsfw",
+        aliases: &[],
+        category: Category::Settings,
+        description: "Shows, enables or disables NSFW content in this server",
+        args: &[ArgSpec::required("action", ArgKind::Text)],
+        subcommands: &[],
+        examples: &["nsfw show", "nsfw off"],
+        owner_only: false,
+        permissions: Permissions::MANAGE_GUILD,
+        slash: false,
+        nsfw_content: false,
+        integration: None,
+        cooldown: Some(Cooldown::per_guild(3, 10)),
+        run: |data| Box::pin(settings::nsfw(data)),
+    },
+    Command {
+        name: "errors",
+        aliases: &[],
+        category: Category::Owner,
+        description: "Lists, shows, searches and clears the stored errors",
+        args: &[
+            ArgSpec::required("action", ArgKind::Text),
+            ArgSpec::optional("query", ArgKind::Rest),
+        ],
+        subcommands: &[Subcommand {
+            name: "show",
+            args: &[ArgSpec::required("id", ArgKind::Integer)],
+        }],
+        examples: &[
+            "errors list",
+            "errors show 42",
+            "errors search timeout",
+            "errors clear --before 2024-01-01",
+        ],
+        owner_only: true,
+        permissions: Permissions::empty(),
+        slash: false,
+        nsfw_content: false,
+        integration: None,
+        cooldown: None,
+        run: |data| Box::pin(owner::errors(data)),
+    },
+    Command {
+        name: "status",
+        aliases: &[],
+        category: Category::Owner,
+        description: "Lists, adds, removes and sets the statuses of the bot",
+        args: &[
+            ArgSpec::required("action", ArgKind::Text),
+            ArgSpec::optional("query", ArgKind::Rest),
+        ],
+        subcommands: &[
+            Subcommand {
+                name: "remove",
+                args: &[ArgSpec::required("id", ArgKind::Integer)],
+            },
+            Subcommand {
+                name: "set",
+                args: &[ArgSpec::required("id", ArgKind::Integer)],
+            },
+            Subcommand {
+                name: "weight",
+                args: &[
+                    ArgSpec::required("id", ArgKind::Integer),
+                    ArgSpec::required("weight", ArgKind::Integer),
+                ],
+            },
+            Subcommand {
+                name: "schedule",
+                args: &[
+                    ArgSpec::required("id", ArgKind::Integer),
+                    ArgSpec::optional("options", ArgKind::Rest),
+                ],
+            },
+        ],
+        examples: &[
+            "status list",
+            "status add playing with {servers} servers",
+            "status weight 3 5",
+            "status schedule 3 days:sat,sun",
+        ],
+        owner_only: true,
+        permissions: Permissions::empty(),
+        slash: false,
+        nsfw_content: false,
+        integration: None,
+        cooldown: None,
+        run: |data| Box::pin(owner::status(data)),
+    },
+    Command {
+        name: "reload",
+        aliases: &[],
+        category: Category::Owner,
+        description: "Reloads the config without restarting the bot",
+        args: &[ArgSpec::required("what", ArgKind::Text)],
+        subcommands: &[],
+        examples: &["reload config"],
+        owner_only: true,
+        permissions: Permissions::empty(),
+        slash: false,
+        nsfw_content: false,
+        integration: None,
+        cooldown: None,
+        run: |data| Box::pin(owner::reload(data)),
+    },
+    Command {
+        name: "db",
+        aliases: &[],
+        category: Category::Owner,
+        description: "Backs up the database",
+        args: &[ArgSpec::required("action", ArgKind::Text)],
+        subcommands: &[],
+        examples: &["db backup"],
+        owner_only: true,
+        permissions: Permissions::empty(),
+        slash: false,
+        nsfw_content: false,
+        integration: None,
+        cooldown: None,
+        run: |data| Box::pin(owner::db(data)),
+    },
+    Command {
+        name: "reactions",
+        aliases: &[],
+        category: Category::Owner,
+        description: "Manages the reactions and aliases of reaction messages like $hug",
+        args: &[
+            ArgSpec::required("action", ArgKind::Text),
+            ArgSpec::optional("query", ArgKind::Rest),
+        ],
+        subcommands: &[Subcommand {
+            name: "remove",
+            args: &[ArgSpec::required("id", ArgKind::Integer)],
+        }],
+        examples: &[
+            "reactions list hug",
+            "reactions add hug https://example.com/hug.gif",
+            "reactions remove 12",
+            "reactions alias cuddle hug",
+            "reactions unalias cuddle",
+        ],
+        owner_only: true,
+        permissions: Permissions::empty(),
+        slash: false,
+        nsfw_content: false,
+        integration: None,
+        cooldown: None,
+        run: |data| Box::pin(owner::reactions(data)),
+    },
+];
+
+/// Looks up a command by its name or one of its aliases.
+///
+/// # Arguments
+/// * `name` - The (lowercase) name the command was invoked with.
+///
+/// # Returns
+/// The matching command, or `None` if no command matches.
+pub fn find_command(name: &str) -> Option<&'static Command> {
+    COMMANDS.iter().find(|cmd| cmd.matches(name))
+}
+
+/// Builds the slash command registrations of all enabled commands that
+/// support them.
+pub fn slash_commands(config: &Config) -> Vec<CreateCommand> {
+    COMMANDS
+        .iter()
+        .filter(|cmd| cmd.slash && cmd.is_enabled(config))
+        .map(Command::slash_command)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(command: &str, input: &str) -> Result<Args> {
+        find_command(command)
+            .unwrap()
+            .parse_message_args(input, "h!")
+    }
+
+    #[test]
+    fn parses_the_arguments_of_subcommands() {
+        let args = parse("status", "weight 3 5").unwrap();
+        assert_eq!(args.text("action"), Some("weight"));
+        assert_eq!(
+            (args.integer("id"), args.integer("weight")),
+            (Some(3), Some(5))
+        );
+
+        let args = parse("status", "SCHEDULE 3 days:sat from:2024-01-01").unwrap();
+        assert_eq!(args.integer("id"), Some(3));
+        assert_eq!(args.text("options"), Some("days:sat from:2024-01-01"));
+
+        let args = parse("status", "add playing 42").unwrap();
+        assert_eq!(args.text("query"), Some("playing 42"));
+        assert_eq!(args.integer("id"), None);
+    }
+
+    #[test]
+    fn rejects_invalid_subcommand_arguments() {
+        let error = parse("errors", "show abc").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid value for argument `id`\nUsage: `h!errors show <id>`"
+        );
+
+        let error = parse("reactions", "remove").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Missing argument `id`\nUsage: `h!reactions remove <id>`"
+        );
+
+        let error = parse("status", "set 1 2").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unexpected argument `2`\nUsage: `h!status set <id>`"
+        );
+
+        let error = parse("status", "").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Missing argument `action`\nUsage: `h!status <action> [query]`"
+        );
+    }
+}
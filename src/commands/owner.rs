@@ -0,0 +1,608 @@
+use anyhow::Result;
+use chrono::{NaiveDate, Weekday};
+use serenity::builder::CreateEmbed;
+
+use crate::{
+    config::RESTART_REQUIRED,
+    db::{
+        backup::create_backup,
+        models::{ErrorLog, Status, StatusType},
+    },
+    helpers::{
+        paginate::{chunk_lines, paginate},
+        types::CommandData,
+        utils::{get_activity, reload_config, render_status},
+    },
+};
+
+const ERRORS_PER_PAGE: usize = 10;
+/// The most errors `errors list` and `errors search` load at once.
+const MAX_LISTED_ERRORS: i64 = 100;
+const STATUSES_PER_PAGE: usize = 15;
+const REACTIONS_PER_PAGE: usize = 10;
+const STATUS_TYPES: [&str; 5] = ["watching", "listening", "playing", "competing", "custom"];
+
+/// Truncates the given text to at most `max` characters, adding an ellipsis
+/// if anything was cut off.
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        let mut truncated = text.chars().take(max.saturating_sub(1)).collect::<String>();
+        truncated.push('…');
+        truncated
+    }
+}
+
+fn error_summary(error: &ErrorLog) -> String {
+    let time = error
+        .timestamp
+        .map_or("Unknown time".to_string(), |ts| format!("<t:{ts}:f>"));
+    let text = error.error.as_deref().unwrap_or("Unknown error");
+    format!("**#{}** {} — {}", error.id, time, truncate(text, 80))
+}
+
+/// Sends the lines as paginated embeds with `per_page` lines each, or the
+/// fallback text if there are none.
+async fn send_lines(
+    data: &CommandData<'_>,
+    title: &str,
+    lines: &[String],
+    per_page: usize,
+    fallback: &str,
+) -> Result<()> {
+    if lines.is_empty() {
+        data.reply(fallback).await?;
+        return Ok(());
+    }
+
+    let pages = chunk_lines(lines, per_page)
+        .into_iter()
+        .map(|description| {
+            CreateEmbed::default()
+                .title(title)
+                .description(description)
+                .color(data.handler.config().embed_colour)
+        })
+        .collect();
+
+    paginate(data, pages).await
+}
+
+/// Sends the given errors as a paginated list of summaries.
+async fn send_error_list(data: &CommandData<'_>, title: &str, errors: &[ErrorLog]) -> Result<()> {
+    let lines = errors.iter().map(error_summary).collect::<Vec<_>>();
+    send_lines(data, title, &lines, ERRORS_PER_PAGE, "No errors found").await
+}
+
+/// Manages the errors stored in the database.
+///
+/// * `errors list` - Lists the newest stored errors.
+/// * `errors show <id>` - Shows all details of a single error.
+/// * `errors search <text>` - Searches the newest error messages, commands and
+///   stacks.
+/// * `errors clear --before <YYYY-MM-DD>` - Deletes all errors before the date.
+pub async fn errors(data: CommandData<'_>) -> Result<()> {
+    let action = data.args.text("action").unwrap_or_default().to_lowercase();
+    let query = data.args.text("query").unwrap_or_default().trim();
+
+    match action.as_str() {
+        "list" => {
+            let errors = data.handler.db.list_errors(MAX_LISTED_ERRORS).await?;
+
+            send_error_list(&data, "Stored errors", &errors).await?;
+        }
+        "show" => {
+            let id = data.args.integer("id").unwrap_or_default();
+
+            let Some(error) = data.handler.db.get_error(id).await? else {
+                data.reply(format!("No error with Id {id} found")).await?;
+                return Ok(());
+            };
+
+            let time = error
+                .timestamp
+                .map_or("Unknown".to_string(), |ts| format!("<t:{ts}:f>"));
+            let stack = error.stack.as_deref().unwrap_or("None");
+
+            let embed = CreateEmbed::default()
+                .title(format!("Error #{}", error.id))
+                .color(data.handler.config().embed_colour)
+                .field("Time", time, true)
+                .field(
+                    "Server",
+                    error.server.as_deref().unwrap_or("Direct Message"),
+                    true,
+                )
+                .field("Channel", format!("<#{}>", error.channel), true)
+                .field("User", format!("<@{}>", error.user), true)
+                .field(
+                    "Command used",
+                    truncate(error.command.as_deref().unwrap_or("None"), 1024),
+                    false,
+                )
+                .field(
+                    "Error",
+                    truncate(error.error.as_deref().unwrap_or("None"), 1024),
+                    false,
+                )
+                .field(
+                    "Stack",
+                    format!("```\n{}\n```", truncate(stack, 1000)),
+                    false,
+                );
+
+            paginate(&data, vec![embed]).await?;
+        }
+        "search" => {
+            if query.is_empty() {
+                data.reply("Please provide something to search for").await?;
+                return Ok(());
+            }
+
+            let errors = data
+                .handler
+                .db
+                .search_errors(query, MAX_LISTED_ERRORS)
+                .await?;
+
+            send_error_list(&data, &format!("Errors matching \"{query}\""), &errors).await?;
+        }
+        "clear" => {
+            let before = query
+                .strip_prefix("--before")
+                .map(str::trim)
+                .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+                .and_then(|date| date.and_hms_opt(0, 0, 0));
+
+            let Some(before) = before else {
+                data.reply(format!(
+                    "Usage: `{}errors clear --before <YYYY-MM-DD>`",
+                    data.prefix
+                ))
+                .await?;
+                return Ok(());
+            };
+
+            let timestamp = before.and_utc().timestamp();
+            let deleted = data.handler.db.delete_errors_before(timestamp).await?;
+
+            data.reply(format!(
+                "Deleted {deleted} error(s) from before {}",
+                before.date()
+            ))
+            .await?;
+        }
+        _ => {
+            data.reply(format!(
+                "Usage: `{}errors <list|show|search|clear> [query]`",
+                data.prefix
+            ))
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses a `YYYY-MM-DD` date into a unix timestamp at the given time of day.
+fn parse_date_timestamp(date: &str, hour: u32, min: u32, sec: u32) -> Option<i64> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .ok()?
+        .and_hms_opt(hour, min, sec)
+        .map(|date| date.and_utc().timestamp())
+}
+
+fn status_summary(status: &Status) -> String {
+    let mut summary = format!(
+        "**#{}** {} {} (weight {})",
+        status.id,
+        status.r#type.as_str(),
+        status.status,
+        status.weight
+    );
+    if let Some(days) = &status.active_days {
+        summary.push_str(&format!(", on {days}"));
+    }
+    if let Some(starts_at) = status.starts_at {
+        summary.push_str(&format!(", from <t:{starts_at}:d>"));
+    }
+    if let Some(ends_at) = status.ends_at {
+        summary.push_str(&format!(", until <t:{ends_at}:d>"));
+    }
+    summary
+}
+
+/// Manages the statuses the bot rotates through. Changes are written to the
+/// database and applied to the running status loop right away.
+///
+/// * `status list` - Lists all statuses.
+/// * `status add <type> <text>` - Adds a new status.
+/// * `status remove <id>` - Removes a status.
+/// * `status set <id>` - Switches to the given status right away.
+/// * `status weight <id> <weight>` - Sets how likely a status is to be picked.
+/// * `status schedule <id> [days:sat,sun] [from:YYYY-MM-DD] [to:YYYY-MM-DD]` -
+///   Limits a status to certain weekdays and/or dates. Without any options the
+///   schedule is cleared.
+pub async fn status(data: CommandData<'_>) -> Result<()> {
+    let action = data.args.text("action").unwrap_or_default().to_lowercase();
+    let query = data.args.text("query").unwrap_or_default().trim();
+
+    match action.as_str() {
+        "list" => {
+            let lines = data
+                .handler
+                .statuses
+                .read()
+                .await
+                .iter()
+                .map(status_summary)
+                .collect::<Vec<_>>();
+
+            send_lines(
+                &data,
+                "Statuses",
+                &lines,
+                STATUSES_PER_PAGE,
+                "No statuses found",
+            )
+            .await?;
+        }
+        "add" => {
+            let Some((status_type, text)) = query
+                .split_once(char::is_whitespace)
+                .map(|(status_type, text)| (status_type.to_lowercase(), text.trim()))
+                .filter(|(status_type, _)| STATUS_TYPES.contains(&status_type.as_str()))
+            else {
+                data.reply(format!(
+                    "Usage: `{}status add <{}> <text>`",
+                    data.prefix,
+                    STATUS_TYPES.join("|")
+                ))
+                .await?;
+                return Ok(());
+            };
+
+            let status_type = StatusType::from(status_type);
+            let type_name = status_type.as_str();
+
+            let mut statuses = data.handler.statuses.write().await;
+            let id = data.handler.db.add_status(&status_type, text).await?;
+
+            statuses.push(Status {
+                id,
+                r#type: status_type,
+                status: text.to_string(),
+                weight: 1,
+                active_days: None,
+                starts_at: None,
+                ends_at: None,
+            });
+            drop(statuses);
+
+            data.reply(format!("Added status **#{id}**: {type_name} {text}"))
+                .await?;
+        }
+        "remove" => {
+            let id = data.args.integer("id").unwrap_or_default();
+
+            let mut statuses = data.handler.statuses.write().await;
+            let removed = data.handler.db.remove_status(id).await?;
+
+            statuses.retain(|status| status.id != id);
+            drop(statuses);
+
+            if !removed {
+                data.reply(format!("No status with Id {id} found")).await?;
+            } else {
+                data.reply(format!("Removed status **#{id}**")).await?;
+            }
+        }
+        "set" => {
+            let id = data.args.integer("id").unwrap_or_default();
+
+            let Some(status) = data
+                .handler
+                .statuses
+                .read()
+                .await
+                .iter()
+                .find(|status| status.id == id)
+                .cloned()
+            else {
+                data.reply(format!("No status with Id {id} found")).await?;
+                return Ok(());
+            };
+
+            let text = render_status(&status.status, data.ctx, data.handler);
+            data.ctx
+                .set_activity(Some(get_activity(&status.r#type, &text)));
+
+            data.reply(format!(
+                "Set status to **#{id}**: {} {text}",
+                status.r#type.as_str(),
+            ))
+            .await?;
+        }
+        "weight" => {
+            let id = data.args.integer("id").unwrap_or_default();
+            let weight = data.args.integer("weight").unwrap_or_default();
+
+            if weight < 0 {
+                data.reply("The weight can't be negative").await?;
+                return Ok(());
+            }
+
+            let mut statuses = data.handler.statuses.write().await;
+            data.handler.db.set_status_weight(id, weight).await?;
+
+            let Some(status) = statuses.iter_mut().find(|status| status.id == id) else {
+                drop(statuses);
+                data.reply(format!("No status with Id {id} found")).await?;
+                return Ok(());
+            };
+            status.weight = weight;
+            drop(statuses);
+
+            data.reply(format!("Set the weight of status **#{id}** to {weight}"))
+                .await?;
+        }
+        "schedule" => {
+            let id = data.args.integer("id").unwrap_or_default();
+            let parts = data
+                .args
+                .text("options")
+                .unwrap_or_default()
+                .split_whitespace();
+
+            let (mut active_days, mut starts_at, mut ends_at) = (None, None, None);
+            for part in parts {
+                let valid = match part.split_once(':') {
+                    Some(("days", days))
+                        if days.split(',').all(|day| day.parse::<Weekday>().is_ok()) =>
+                    {
+                        active_days = Some(days.to_lowercase());
+                        true
+                    }
+                    Some(("from", date)) => {
+                        starts_at = parse_date_timestamp(date, 0, 0, 0);
+                        starts_at.is_some()
+                    }
+                    Some(("to", date)) => {
+                        ends_at = parse_date_timestamp(date, 23, 59, 59);
+                        ends_at.is_some()
+                    }
+                    _ => false,
+                };
+
+                if !valid {
+                    data.reply(format!("Invalid schedule option `{part}`"))
+                        .await?;
+                    return Ok(());
+                }
+            }
+
+            if starts_at.zip(ends_at).is_some_and(|(from, to)| from > to) {
+                data.reply("The schedule can't end before it starts")
+                    .await?;
+                return Ok(());
+            }
+
+            let mut statuses = data.handler.statuses.write().await;
+            data.handler
+                .db
+                .set_status_schedule(id, active_days.as_deref(), starts_at, ends_at)
+                .await?;
+
+            let Some(status) = statuses.iter_mut().find(|status| status.id == id) else {
+                drop(statuses);
+                data.reply(format!("No status with Id {id} found")).await?;
+                return Ok(());
+            };
+            status.active_days = active_days;
+            status.starts_at = starts_at;
+            status.ends_at = ends_at;
+            let summary = status_summary(status);
+            drop(statuses);
+
+            data.reply(format!("Updated the schedule of {summary}"))
+                .await?;
+        }
+        _ => {
+            data.reply(format!(
+                "Usage: `{}status <list|add|remove|set|weight|schedule> [query]`",
+                data.prefix
+            ))
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Manages the reactions and aliases used by reaction messages such as
+/// `$hug @user`. Changes take effect immediately.
+///
+/// * `reactions list [command]` - Lists all reactions, or those of a command.
+/// * `reactions add <command> <reaction>` - Adds a GIF link or text reaction.
+/// * `reactions remove <id>` - Removes a reaction.
+/// * `reactions aliases` - Lists all aliases.
+/// * `reactions alias <alias> <command>` - Adds an alias for a command.
+/// * `reactions unalias <alias>` - Removes an alias.
+pub async fn reactions(data: CommandData<'_>) -> Result<()> {
+    let action = data.args.text("action").unwrap_or_default().to_lowercase();
+    let query = data.args.text("query").unwrap_or_default().trim();
+    let db = &data.handler.db;
+
+    let (first, rest) = query
+        .split_once(char::is_whitespace)
+        .map_or((query, ""), |(first, rest)| (first, rest.trim()));
+    let first = first.trim_start_matches('$').to_lowercase();
+
+    match action.as_str() {
+        "list" => {
+            let command = (!first.is_empty()).then_some(first.as_str());
+            let reactions = db.list_reactions(command).await?;
+
+            let lines = reactions
+                .iter()
+                .map(|reaction| {
+                    format!(
+                        "**#{}** ${} {}",
+                        reaction.id,
+                        reaction.command,
+                        truncate(&reaction.reaction, 80)
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            send_lines(
+                &data,
+                "Reactions",
+                &lines,
+                REACTIONS_PER_PAGE,
+                "No reactions found",
+            )
+            .await?;
+        }
+        "add" => {
+            if first.is_empty() || rest.is_empty() {
+                data.reply(format!(
+                    "Usage: `{}reactions add <command> <reaction>`",
+                    data.prefix
+                ))
+                .await?;
+                return Ok(());
+            }
+
+            let id = db.add_reaction(&first, rest).await?;
+
+            data.reply(format!("Added reaction **#{id}** to `${first}`"))
+                .await?;
+        }
+        "remove" => {
+            let id = data.args.integer("id").unwrap_or_default();
+
+            if !db.remove_reaction(id).await? {
+                data.reply(format!("No reaction with Id {id} found"))
+                    .await?;
+            } else {
+                data.reply(format!("Removed reaction **#{id}**")).await?;
+            }
+        }
+        "aliases" => {
+            let aliases = db.list_aliases().await?;
+
+            let lines = aliases
+                .iter()
+                .map(|alias| format!("`${}` → `${}`", alias.alias, alias.command))
+                .collect::<Vec<_>>();
+
+            send_lines(
+                &data,
+                "Reaction aliases",
+                &lines,
+                REACTIONS_PER_PAGE,
+                "No aliases found",
+            )
+            .await?;
+        }
+        "alias" => {
+            let command = rest.trim_start_matches('$').to_lowercase();
+            if first.is_empty() || command.is_empty() {
+                data.reply(format!(
+                    "Usage: `{}reactions alias <alias> <command>`",
+                    data.prefix
+                ))
+                .await?;
+                return Ok(());
+            }
+
+            db.set_alias(&first, &command).await?;
+
+            data.reply(format!("`${first}` is now an alias for `${command}`"))
+                .await?;
+        }
+        "unalias" => {
+            if !db.remove_alias(&first).await? {
+                data.reply(format!("No alias called `${first}` found"))
+                    .await?;
+            } else {
+                data.reply(format!("Removed the alias `${first}`")).await?;
+            }
+        }
+        _ => {
+            data.reply(format!(
+                "Usage: `{}reactions <list|add|remove|aliases|alias|unalias> [query]`",
+                data.prefix
+            ))
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reloads parts of the bot without restarting it.
+///
+/// * `reload config` - Reads the config again and swaps it in, keeping the
+///   current one if the new one is invalid. Also done on SIGHUP.
+pub async fn reload(data: CommandData<'_>) -> Result<()> {
+    let what = data.args.text("what").unwrap_or_default().to_lowercase();
+
+    if what != "config" {
+        data.reply(format!("Usage: `{}reload config`", data.prefix))
+            .await?;
+        return Ok(());
+    }
+
+    let reply = match reload_config(data.ctx, data.handler).await {
+        Ok(changed) if changed.is_empty() => "Reloaded the config, nothing changed".to_string(),
+        Ok(changed) => {
+            let changed = changed
+                .iter()
+                .map(|key| {
+                    if RESTART_REQUIRED.contains(key) {
+                        format!("`{key}` (takes effect after a restart)")
+                    } else {
+                        format!("`{key}`")
+                    }
+                })
+                .collect::<Vec<_>>();
+            format!("Reloaded the config, changed: {}", changed.join(", "))
+        }
+        Err(e) => format!("Kept the current config, the new one is invalid:\n{e}"),
+    };
+
+    data.reply(reply).await?;
+
+    Ok(())
+}
+
+/// Maintains the database.
+///
+/// * `db backup` - Writes a snapshot of the database to the backup directory,
+///   deleting the oldest backups exceeding the retention. Also done every
+///   `backup_interval` hours.
+pub async fn db(data: CommandData<'_>) -> Result<()> {
+    let action = data.args.text("action").unwrap_or_default().to_lowercase();
+
+    if action != "backup" {
+        data.reply(format!("Usage: `{}db backup`", data.prefix))
+            .await?;
+        return Ok(());
+    }
+
+    if !data.handler.db.supports_backups() {
+        data.reply("Backups are only supported with SQLite").await?;
+        return Ok(());
+    }
+
+    let config = data.handler.config();
+    let path = create_backup(data.handler.db.as_ref(), &config).await?;
+
+    data.reply(format!("Backed up the database to `{}`", path.display()))
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use anyhow::{anyhow, Result};
+
+use crate::helpers::{
+    types::CommandData,
+    utils::{add_prefix, nsfw_enabled, remove_prefix, set_nsfw_enabled, set_prefix},
+};
+
+const MAX_PREFIX_LENGTH: usize = 5;
+const MAX_PREFIXES: usize = 5;
+
+/// Checks that the prefix is usable, returning a message explaining why it
+/// isn't otherwise.
+fn validate_prefix(prefix: &str) -> Result<(), String> {
+    if prefix.is_empty() {
+        return Err("The prefix can't be empty".to_string());
+    }
+    if prefix.chars().count() > MAX_PREFIX_LENGTH {
+        return Err(format!(
+            "The prefix can't be longer than {MAX_PREFIX_LENGTH} characters"
+        ));
+    }
+    if prefix.chars().any(char::is_whitespace) {
+        return Err("The prefix can't contain any whitespace".to_string());
+    }
+    // Messages starting with `$` are reaction commands like `$hug`.
+    if prefix.starts_with('$') {
+        return Err("The prefix can't start with `$`, it's used for reactions".to_string());
+    }
+    Ok(())
+}
+
+/// Manages the prefixes of the current server.
+/// The bot can always be mentioned instead of using a prefix as well.
+///
+/// * `prefix show` - Shows the current prefixes.
+/// * `prefix set <prefix>` - Replaces all prefixes with the given one.
+/// * `prefix add <prefix>` - Adds another prefix.
+/// * `prefix remove <prefix>` - Removes one of the prefixes.
+/// * `prefix reset` - Resets the prefixes to the default one.
+pub async fn prefix(data: CommandData<'_>) -> Result<()> {
+    let guild_id = data
+        .guild_id()
+        .ok_or_else(|| anyhow!("This command can only be used in a server"))?;
+
+    let action = data.args.text("action").unwrap_or_default().to_lowercase();
+    let current = data
+        .handler
+        .prefixes
+        .read()
+        .await
+        .get(&guild_id.to_string())
+        .cloned()
+        .unwrap_or_default();
+
+    let new_prefix = match action.as_str() {
+        "set" | "add" | "remove" => match data.args.text("prefix") {
+            Some(prefix) => prefix,
+            None => {
+                data.reply(format!("Usage: `{}prefix {action} <prefix>`", data.prefix))
+                    .await?;
+                return Ok(());
+            }
+        },
+        _ => "",
+    };
+
+    let reply = match action.as_str() {
+        "show" => {
+            let prefixes = if current.is_empty() {
+                format!("`{}`", data.handler.config().default_prefix)
+            } else {
+                current
+                    .iter()
+                    .map(|prefix| format!("`{prefix}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            format!("The prefixes of this server are: {prefixes}")
+        }
+        "set" => match validate_prefix(new_prefix) {
+            Ok(()) => {
+                set_prefix(guild_id, new_prefix, data.handler).await?;
+                format!("Set the prefix of this server to `{new_prefix}`")
+            }
+            Err(reason) => reason,
+        },
+        "add" => match validate_prefix(new_prefix) {
+            Ok(()) if current.iter().any(|prefix| prefix == new_prefix) => {
+                format!("`{new_prefix}` is already a prefix of this server")
+            }
+            Ok(()) if current.len() >= MAX_PREFIXES => {
+                format!("A server can't have more than {MAX_PREFIXES} prefixes")
+            }
+            Ok(()) => {
+                add_prefix(guild_id, new_prefix, data.handler).await?;
+                format!("Added `{new_prefix}` as a prefix of this server")
+            }
+            Err(reason) => reason,
+        },
+        "remove" => {
+            if current.len() <= 1 {
+                format!(
+                    "Can't remove the only prefix of this server, use `{}prefix set` instead",
+                    data.prefix
+                )
+            } else if remove_prefix(guild_id, new_prefix, data.handler).await? {
+                format!("Removed `{new_prefix}` from the prefixes of this server")
+            } else {
+                format!("`{new_prefix}` is not a prefix of this server")
+            }
+        }
+        "reset" => {
+            let default_prefix = data.handler.config().default_prefix.clone();
+            set_prefix(guild_id, &default_prefix, data.handler).await?;
+            format!("Reset the prefix of this server to `{default_prefix}`")
+        }
+        _ => format!(
+            "Usage: `{}prefix <show|set|add|remove|reset> [prefix]`",
+            data.prefix
+        ),
+    };
+
+    data.reply(reply).await?;
+
+    Ok(())
+}
+
+/// Manages whether NSFW content may be posted in this server. Even when
+/// enabled, it is only ever posted in age-restricted channels.
+///
+/// * `nsfw show` - Shows whether NSFW content is enabled.
+/// * `nsfw on` - Enables NSFW content.
+/// * `nsfw off` - Disables NSFW content.
+pub async fn nsfw(data: CommandData<'_>) -> Result<()> {
+    let guild_id = data
+        .guild_id()
+        .ok_or_else(|| anyhow!("This command can only be used in a server"))?;
+
+    let action = data.args.text("action").unwrap_or_default().to_lowercase();
+
+    let reply = match action.as_str() {
+        "show" => {
+            if nsfw_enabled(guild_id, data.handler).await? {
+                "NSFW content is enabled in the age-restricted channels of this server"
+            } else {
+                "NSFW content is disabled in this server"
+            }
+        }
+        "on" | "enable" => {
+            set_nsfw_enabled(guild_id, true, data.handler).await?;
+            "Enabled NSFW content in the age-restricted channels of this server"
+        }
+        "off" | "disable" => {
+            set_nsfw_enabled(guild_id, false, data.handler).await?;
+            "Disabled NSFW content in this server"
+        }
+        _ => {
+            data.reply(format!("Usage: `{}nsfw <show|on|off>`", data.prefix))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    data.reply(reply).await?;
+
+    Ok(())
+}
@@ -1,72 +1,725 @@
-use std::{env, process};
+use std::{
+    collections::HashMap, env, fmt::Display, fs, io::ErrorKind, num::NonZeroU64, str::FromStr,
+};
 
-use serenity::{all::UserId, model::Colour};
+use anyhow::{anyhow, Result};
+use serenity::{
+    all::{GatewayIntents, UserId},
+    model::Colour,
+};
+use toml::{Table, Value};
 
-use crate::helpers::{types::Owners, utils::is_indev};
+use crate::helpers::types::Owners;
 
-#[allow(dead_code)]
+/// The file the config is read from if `CONFIG_PATH` isn't set. Unlike an
+/// explicitly set path, it doesn't have to exist.
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+/// The keys that are only used while connecting, so changing them only takes
+/// effect after a restart.
+pub const RESTART_REQUIRED: &[&str] = &["bot_token", "intents"];
+
+/// The most hours `backup_interval` can be set to, a year.
+const MAX_BACKUP_INTERVAL: u64 = 365 * 24;
+
+/// A third-party service the bot integrates with. Each one is only enabled
+/// if all of its credentials are configured, commands relying on a disabled
+/// integration are disabled as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Integration {
+    Exchange,
+    Imgur,
+    Reddit,
+}
+
+impl Integration {
+    pub const ALL: [Integration; 3] = [
+        Integration::Exchange,
+        Integration::Imgur,
+        Integration::Reddit,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Integration::Exchange => "Exchange",
+            Integration::Imgur => "Imgur",
+            Integration::Reddit => "Reddit",
+        }
+    }
+}
+
+/// The configuration of the bot.
+///
+/// Every value is read from the TOML file at `CONFIG_PATH` (`config.toml` by
+/// default) and can be overridden by an environment variable named like its
+/// key in uppercase, e.g. `log_channel` by `LOG_CHANNEL`. Lists are given as
+/// comma separated values in environment variables.
 #[derive(Debug)]
-pub struct Config<'a> {
+pub struct Config {
     pub bot_token: String,
     pub exchange_api_key: String,
+    /// The base URL of the exchange rate API, configurable to test against a
+    /// mock.
+    pub exchange_api_url: String,
+    /// How many seconds cached exchange rates are used before they are
+    /// fetched again.
+    pub exchange_cache_ttl: i64,
     pub imgur_client_id: String,
-    pub imgur_client_secret: String,
+    /// The base URL of the Imgur API, configurable to test against a stub.
+    pub imgur_api_url: String,
     pub reddit_client_id: String,
     pub reddit_client_secret: String,
     pub reddit_refresh_token: String,
-    pub dev_mode: bool,
+    /// The base URL of the Reddit API, configurable to test against a mock.
+    pub reddit_api_url: String,
+    /// The URL OAuth access tokens are requested from.
+    pub reddit_auth_url: String,
     pub embed_colour: Colour,
-    pub dev_channels: &'a [u64],
+    pub dev_channels: Vec<u64>,
     pub bot_owners: Owners,
     pub log_channel: u64,
+    /// The prefix used for guilds that haven't set their own and for DMs.
+    pub default_prefix: String,
+    /// The gateway intents the bot connects with.
+    pub intents: GatewayIntents,
+    /// The minimum number of seconds between two status changes.
+    pub status_interval_min: u64,
+    /// The maximum number of seconds between two status changes.
+    pub status_interval_max: u64,
+    /// The directory database backups are written to.
+    pub backup_dir: String,
+    /// How many hours pass between two scheduled backups, 0 disables them.
+    pub backup_interval: u64,
+    /// How many of the newest backups are kept, older ones are deleted.
+    pub backup_retention: usize,
 }
-#[allow(clippy::unreadable_literal)]
-impl Config<'_> {
-    pub fn new() -> Self {
+
+/// Reads raw config values, preferring environment variables over the config
+/// file, and collects every invalid value so they can be reported at once.
+struct ConfigSource {
+    path: String,
+    file: Table,
+    /// The environment variables, read once so all values come from the same
+    /// environment.
+    env: HashMap<String, String>,
+    errors: Vec<String>,
+}
+
+impl ConfigSource {
+    /// Reads the config file at `CONFIG_PATH`, or `config.toml` if it isn't
+    /// set.
+    ///
+    /// # Errors
+    /// * If the file can't be read or isn't valid TOML. A missing file is only
+    ///   an error if its path was set explicitly.
+    fn load() -> Result<Self> {
+        let explicit_path = env::var("CONFIG_PATH").ok();
+        let path = explicit_path
+            .clone()
+            .unwrap_or_else(|| DEFAULT_CONFIG_PATH.to_string());
+
+        let file = match fs::read_to_string(&path) {
+            Ok(contents) => contents
+                .parse::<Table>()
+                .map_err(|e| anyhow!("Failed to parse config file {path}: {e}"))?,
+            Err(e) if e.kind() == ErrorKind::NotFound && explicit_path.is_none() => Table::new(),
+            Err(e) => return Err(anyhow!("Failed to read config file {path}: {e}")),
+        };
+
+        // Variables that aren't valid UTF-8 can't hold valid values anyway.
+        let env = env::vars_os()
+            .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+            .collect();
+
+        Ok(Self {
+            path,
+            file,
+            env,
+            errors: Vec::new(),
+        })
+    }
+
+    /// Returns the raw values of the key, split into a list if `list` is
+    /// set, together with where they came from.
+    fn raw(&self, key: &str, list: bool) -> Option<(Vec<String>, String)> {
+        let env_key = key.to_uppercase();
+
+        if let Some(value) = self.env.get(&env_key) {
+            let values = if list {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(ToString::to_string)
+                    .collect()
+            } else {
+                vec![value.clone()]
+            };
+            return Some((values, format!("environment variable {env_key}")));
+        }
+
+        let to_string = |value: &Value| match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+
+        let values = match self.file.get(key)? {
+            Value::Array(values) if list => values.iter().map(to_string).collect(),
+            value => vec![to_string(value)],
+        };
+
+        Some((values, format!("`{key}` in {}", self.path)))
+    }
+
+    /// Parses the value of the key, recording an error if it is invalid.
+    ///
+    /// # Returns
+    /// The value, or `None` if it isn't set or invalid.
+    fn get<T: FromStr>(&mut self, key: &str) -> Option<T>
+    where
+        T::Err: Display,
+    {
+        let (values, origin) = self.raw(key, false)?;
+        let value = values.into_iter().next().unwrap_or_default();
+
+        match value.parse() {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.errors
+                    .push(format!("{origin}: `{value}` is not valid, {e}"));
+                None
+            }
+        }
+    }
+
+    /// Parses the value of the key like [`Self::get`], falling back to the
+    /// default if it isn't set.
+    fn get_or<T: FromStr>(&mut self, key: &str, default: T) -> T
+    where
+        T::Err: Display,
+    {
+        self.get(key).unwrap_or(default)
+    }
+
+    /// Parses the value of the key, recording an error if it isn't set or
+    /// is blank.
+    fn require<T: FromStr>(&mut self, key: &str) -> Option<T>
+    where
+        T::Err: Display,
+    {
+        match self.raw(key, false) {
+            None => {
+                self.errors.push(format!(
+                    "`{key}` is required, set it in {} or as {}",
+                    self.path,
+                    key.to_uppercase()
+                ));
+                None
+            }
+            Some((values, origin)) if values.iter().all(|value| value.trim().is_empty()) => {
+                self.errors.push(format!("{origin} can't be empty"));
+                None
+            }
+            Some(_) => self.get(key),
+        }
+    }
+
+    /// Parses every element of the list under the key, recording an error
+    /// for each invalid one.
+    fn list<T: FromStr>(&mut self, key: &str) -> Option<Vec<T>>
+    where
+        T::Err: Display,
+    {
+        let (values, origin) = self.raw(key, true)?;
+        let mut parsed = Vec::new();
+
+        for value in values {
+            match value.parse() {
+                Ok(value) => parsed.push(value),
+                Err(e) => self
+                    .errors
+                    .push(format!("{origin}: `{value}` is not valid, {e}")),
+            }
+        }
+
+        Some(parsed)
+    }
+
+    /// Returns the value of the key if it is an integer in the config file and
+    /// isn't overridden by an environment variable.
+    fn file_integer(&self, key: &str) -> Option<i64> {
+        if self.env.contains_key(&key.to_uppercase()) {
+            return None;
+        }
+
+        self.file.get(key).and_then(Value::as_integer)
+    }
+
+    /// Records an error for the key if the check failed.
+    fn check(&mut self, ok: bool, key: &str, message: &str) {
+        if !ok {
+            self.errors.push(format!("`{key}` {message}"));
+        }
+    }
+}
+
+/// Parses a colour given as hex, with or without a leading `#`.
+fn parse_colour(value: &str) -> Result<Colour, String> {
+    let hex = value.trim_start_matches('#').trim_start_matches("0x");
+    u32::from_str_radix(hex, 16)
+        .ok()
+        .filter(|colour| *colour <= 0xFF_FF_FF)
+        .map(Colour::from)
+        .ok_or_else(|| format!("`{value}` is not a hex colour like #CE3A9B"))
+}
+
+/// Parses the name of a gateway intent, like `guild_messages`. `all` and
+/// `non_privileged` stand for the respective groups of intents.
+fn parse_intent(name: &str) -> Result<GatewayIntents, String> {
+    match name.to_lowercase().as_str() {
+        "all" => Ok(GatewayIntents::all()),
+        "non_privileged" => Ok(GatewayIntents::non_privileged()),
+        lower => GatewayIntents::from_name(&lower.to_uppercase())
+            .ok_or_else(|| format!("`{name}` is not a gateway intent")),
+    }
+}
+
+impl Config {
+    /// Loads the config from the config file and the environment.
+    ///
+    /// # Errors
+    /// * If the config file can't be read, or any value is missing or
+    ///   invalid. All invalid values are listed in the error.
+    pub fn load() -> Result<Self> {
+        Self::from_source(ConfigSource::load()?)
+    }
+
+    /// Parses a config from TOML with the given environment variables, so
+    /// tests depend neither on a config file nor on the real environment.
+    #[cfg(test)]
+    pub fn from_toml(toml: &str, env: &[(&str, &str)]) -> Result<Self> {
+        Self::from_source(ConfigSource {
+            path: "the test config".to_string(),
+            file: toml.parse()?,
+            env: env
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            errors: Vec::new(),
+        })
+    }
+
+    fn from_source(mut source: ConfigSource) -> Result<Self> {
+        // Integers in the config file, including hex ones like `0xCE3A9B`,
+        // are taken as they are. Strings are always parsed as hex.
+        let embed_colour = match source.file_integer("embed_colour") {
+            Some(colour) => u32::try_from(colour)
+                .ok()
+                .filter(|colour| *colour <= 0xFF_FF_FF)
+                .map(Colour::from)
+                .ok_or_else(|| format!("`{colour}` is not a colour between 0 and 0xFFFFFF")),
+            None => source
+                .get::<String>("embed_colour")
+                .map_or(Ok(Colour::from(0xCE_3A_9B)), |colour| parse_colour(&colour)),
+        }
+        .unwrap_or_else(|e| {
+            source.errors.push(format!("`embed_colour`: {e}"));
+            Colour::default()
+        });
+
+        let intents = source
+            .list::<String>("intents")
+            .unwrap_or_else(|| vec!["all".to_string()])
+            .iter()
+            .map(|name| parse_intent(name))
+            .collect::<Result<Vec<_>, _>>()
+            .map(|intents| intents.into_iter().collect::<GatewayIntents>())
+            .unwrap_or_else(|e| {
+                source.errors.push(format!("`intents`: {e}"));
+                GatewayIntents::empty()
+            });
+
+        let bot_token = source.require::<String>("bot_token");
+        let bot_owner = source.require::<NonZeroU64>("bot_owner");
+        let log_channel = source.require::<NonZeroU64>("log_channel");
+        let secondary_owners = source
+            .list::<NonZeroU64>("secondary_owners")
+            .unwrap_or_default();
+        let dev_channels = source.list::<u64>("dev_channels").unwrap_or_default();
+        let default_prefix = source.get_or("default_prefix", "h!".to_string());
+        let exchange_cache_ttl = source.get_or("exchange_cache_ttl", 3600);
+        let status_interval_min = source.get_or("status_interval_min", 300);
+        let status_interval_max = source.get_or("status_interval_max", 900);
+        let backup_dir = source.get_or("backup_dir", "backups".to_string());
+        let backup_interval = source.get_or("backup_interval", 24);
+        let backup_retention = source.get_or("backup_retention", 7);
+
+        source.check(
+            !default_prefix.is_empty()
+                && !default_prefix.chars().any(char::is_whitespace)
+                && !default_prefix.starts_with('$'),
+            "default_prefix",
+            "can't be empty, contain whitespace or start with `$`",
+        );
+        source.check(
+            exchange_cache_ttl >= 0,
+            "exchange_cache_ttl",
+            "can't be negative",
+        );
+        source.check(
+            status_interval_min > 0,
+            "status_interval_min",
+            "has to be at least 1",
+        );
+        source.check(
+            status_interval_min <= status_interval_max,
+            "status_interval_max",
+            "can't be smaller than status_interval_min",
+        );
+        source.check(!backup_dir.is_empty(), "backup_dir", "can't be empty");
+        source.check(
+            backup_interval <= MAX_BACKUP_INTERVAL,
+            "backup_interval",
+            &format!("can't be more than {MAX_BACKUP_INTERVAL} hours"),
+        );
+        source.check(
+            backup_retention > 0,
+            "backup_retention",
+            "has to be at least 1",
+        );
+
+        let (Some(bot_token), Some(bot_owner), Some(log_channel), true) =
+            (bot_token, bot_owner, log_channel, source.errors.is_empty())
+        else {
+            return Err(anyhow!("Invalid config:\n- {}", source.errors.join("\n- ")));
+        };
+
         let config = Config {
-            bot_token: env::var("BOT_TOKEN").unwrap_or_default(),
-            exchange_api_key: env::var("EXCHANGE_API_KEY").unwrap_or_default(),
-            imgur_client_id: env::var("IMGUR_CLIENT_ID").unwrap_or_default(),
-            imgur_client_secret: env::var("IMGUR_CLIENT_SECRET").unwrap_or_default(),
-            reddit_client_id: env::var("REDDIT_CLIENT_ID").unwrap_or_default(),
-            reddit_client_secret: env::var("REDDIT_CLIENT_SECRET").unwrap_or_default(),
-            reddit_refresh_token: env::var("REDDIT_REFRESH_TOKEN").unwrap_or_default(),
-            dev_mode: is_indev(),
-            embed_colour: Colour::from(0xCE_3A_9B),
-            dev_channels: &[655484859405303809, 551588329003548683, 922679249058553857],
+            bot_token,
+            exchange_api_key: source.get_or("exchange_api_key", String::new()),
+            exchange_api_url: source.get_or(
+                "exchange_api_url",
+                "https://v6.exchangerate-api.com/v6".to_string(),
+            ),
+            exchange_cache_ttl,
+            imgur_client_id: source.get_or("imgur_client_id", String::new()),
+            imgur_api_url: source.get_or("imgur_api_url", "https://api.imgur.com".to_string()),
+            reddit_client_id: source.get_or("reddit_client_id", String::new()),
+            reddit_client_secret: source.get_or("reddit_client_secret", String::new()),
+            reddit_refresh_token: source.get_or("reddit_refresh_token", String::new()),
+            reddit_api_url: source.get_or("reddit_api_url", "https://oauth.reddit.com".to_string()),
+            reddit_auth_url: source.get_or(
+                "reddit_auth_url",
+                "https://www.reddit.com/api/v1/access_token".to_string(),
+            ),
+            embed_colour,
+            dev_channels,
             bot_owners: Owners {
-                primary: UserId::from(258993932262834188),
-                secondary: vec![UserId::from(207505077013839883)],
+                primary: UserId::from(bot_owner),
+                secondary: secondary_owners.into_iter().map(UserId::from).collect(),
             },
-            log_channel: 655484804405657642,
+            log_channel: log_channel.get(),
+            default_prefix,
+            intents,
+            status_interval_min,
+            status_interval_max,
+            backup_dir,
+            backup_interval,
+            backup_retention,
         };
 
-        let missing_credentials = &config.check_config();
+        Ok(config)
+    }
+
+    /// Lists the keys whose values differ between the two configs.
+    pub fn changed_fields(&self, other: &Config) -> Vec<&'static str> {
+        let fields = [
+            ("bot_token", self.bot_token != other.bot_token),
+            (
+                "exchange_api_key",
+                self.exchange_api_key != other.exchange_api_key,
+            ),
+            (
+                "exchange_api_url",
+                self.exchange_api_url != other.exchange_api_url,
+            ),
+            (
+                "exchange_cache_ttl",
+                self.exchange_cache_ttl != other.exchange_cache_ttl,
+            ),
+            (
+                "imgur_client_id",
+                self.imgur_client_id != other.imgur_client_id,
+            ),
+            ("imgur_api_url", self.imgur_api_url != other.imgur_api_url),
+            (
+                "reddit_client_id",
+                self.reddit_client_id != other.reddit_client_id,
+            ),
+            (
+                "reddit_client_secret",
+                self.reddit_client_secret != other.reddit_client_secret,
+            ),
+            (
+                "reddit_refresh_token",
+                self.reddit_refresh_token != other.reddit_refresh_token,
+            ),
+            (
+                "reddit_api_url",
+                self.reddit_api_url != other.reddit_api_url,
+            ),
+            (
+                "reddit_auth_url",
+                self.reddit_auth_url != other.reddit_auth_url,
+            ),
+            ("embed_colour", self.embed_colour != other.embed_colour),
+            ("dev_channels", self.dev_channels != other.dev_channels),
+            (
+                "bot_owner",
+                self.bot_owners.primary != other.bot_owners.primary,
+            ),
+            (
+                "secondary_owners",
+                self.bot_owners.secondary != other.bot_owners.secondary,
+            ),
+            ("log_channel", self.log_channel != other.log_channel),
+            (
+                "default_prefix",
+                self.default_prefix != other.default_prefix,
+            ),
+            ("intents", self.intents != other.intents),
+            (
+                "status_interval_min",
+                self.status_interval_min != other.status_interval_min,
+            ),
+            (
+                "status_interval_max",
+                self.status_interval_max != other.status_interval_max,
+            ),
+            ("backup_dir", self.backup_dir != other.backup_dir),
+            (
+                "backup_interval",
+                self.backup_interval != other.backup_interval,
+            ),
+            (
+                "backup_retention",
+                self.backup_retention != other.backup_retention,
+            ),
+        ];
+
+        fields
+            .into_iter()
+            .filter(|(_, changed)| *changed)
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    /// Logs every missing credential and the integrations that are disabled
+    /// because of them.
+    pub fn report_missing_credentials(&self) {
+        let missing = Integration::ALL
+            .iter()
+            .flat_map(|integration| self.missing_credentials(*integration))
+            .collect::<Vec<_>>();
+
+        if !missing.is_empty() {
+            let disabled = Integration::ALL
+                .iter()
+                .filter(|integration| !self.is_enabled(**integration))
+                .map(|integration| integration.name())
+                .collect::<Vec<_>>();
+
+            warn!("Missing credentials: {}", missing.join(", "));
+            warn!("Disabled integrations: {}", disabled.join(", "));
+        }
+    }
+
+    /// Lists the names of all credentials of the integration that aren't
+    /// configured.
+    pub fn missing_credentials(&self, integration: Integration) -> Vec<&'static str> {
+        let credentials: &[(&str, &str)] = match integration {
+            Integration::Exchange => &[("exchange_api_key", &self.exchange_api_key)],
+            // Anonymous uploads only need the client Id.
+            Integration::Imgur => &[("imgur_client_id", &self.imgur_client_id)],
+            Integration::Reddit => &[
+                ("reddit_client_id", &self.reddit_client_id),
+                ("reddit_client_secret", &self.reddit_client_secret),
+                ("reddit_refresh_token", &self.reddit_refresh_token),
+            ],
+        };
+
+        credentials
+            .iter()
+            .filter(|(_, value)| value.is_empty())
+            .map(|(name, _)| *name)
+            .collect()
+    }
+
+    /// Checks if all credentials of the integration are configured.
+    pub fn is_enabled(&self, integration: Integration) -> bool {
+        self.missing_credentials(integration).is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REQUIRED: &str = "bot_token = \"token\"\nbot_owner = 1\nlog_channel = 2\n";
+
+    fn config(extra: &str) -> Result<Config> {
+        Config::from_toml(&format!("{REQUIRED}{extra}"), &[])
+    }
+
+    #[test]
+    fn bounds_the_backup_interval() {
+        assert_eq!(config("").unwrap().backup_interval, 24);
+        assert_eq!(
+            config("backup_interval = 8760").unwrap().backup_interval,
+            8760
+        );
+
+        let error = config("backup_interval = 8761").unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("`backup_interval` can't be more than 8760 hours"));
+    }
+
+    #[test]
+    fn reads_values_from_toml() {
+        let config = config(
+            r#"
+            secondary_owners = [3, 4]
+            dev_channels = [5]
+            default_prefix = "h?"
+            intents = ["guild_messages", "message_content"]
+            exchange_cache_ttl = 60
+            imgur_client_id = "imgur"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.bot_token, "token");
+        assert_eq!(config.bot_owners.primary, UserId::new(1));
+        assert_eq!(
+            config.bot_owners.secondary,
+            [UserId::new(3), UserId::new(4)]
+        );
+        assert_eq!(config.log_channel, 2);
+        assert_eq!(config.dev_channels, [5]);
+        assert_eq!(config.default_prefix, "h?");
+        assert_eq!(
+            config.intents,
+            GatewayIntents::GUILD_MESSAGES | GatewayIntents::MESSAGE_CONTENT
+        );
+        assert_eq!(config.exchange_cache_ttl, 60);
+        assert_eq!(config.imgur_client_id, "imgur");
+        assert_eq!(config.reddit_client_id, "");
+    }
+
+    #[test]
+    fn environment_variables_override_the_file() {
+        let config = Config::from_toml(
+            &format!("{REQUIRED}default_prefix = \"h?\"\nembed_colour = 255\n"),
+            &[
+                ("DEFAULT_PREFIX", "h."),
+                ("SECONDARY_OWNERS", "3, 4,"),
+                ("EMBED_COLOUR", "00FF00"),
+                ("LOG_CHANNEL", "6"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(config.default_prefix, "h.");
+        assert_eq!(
+            config.bot_owners.secondary,
+            [UserId::new(3), UserId::new(4)]
+        );
+        assert_eq!(config.embed_colour, Colour::from(0x00_FF_00));
+        assert_eq!(config.log_channel, 6);
+    }
+
+    #[test]
+    fn reads_colours_as_hex_strings_or_integers() {
+        let colour =
+            |value: &str| config(&format!("embed_colour = {value}")).map(|c| c.embed_colour);
+
+        assert_eq!(config("").unwrap().embed_colour, Colour::from(0xCE_3A_9B));
+        assert_eq!(colour("16711680").unwrap(), Colour::from(0xFF_00_00));
+        assert_eq!(colour("0xCE3A9B").unwrap(), Colour::from(0xCE_3A_9B));
+        assert_eq!(colour("\"#00ff00\"").unwrap(), Colour::from(0x00_FF_00));
+        assert_eq!(colour("\"0x0000FF\"").unwrap(), Colour::from(0x00_00_FF));
+
+        assert!(colour("16777216").is_err());
+        assert!(colour("-1").is_err());
+        assert!(colour("\"red\"").is_err());
+    }
 
-        if !missing_credentials.is_empty() {
-            println!("Missing credentials: {missing_credentials:?}");
-            process::exit(1);
+    #[test]
+    fn requires_the_required_keys() {
+        let error = Config::from_toml("", &[]).unwrap_err().to_string();
+        for key in ["bot_token", "bot_owner", "log_channel"] {
+            assert!(error.contains(&format!("`{key}` is required")), "{error}");
         }
 
-        config
+        let error = Config::from_toml("bot_owner = 1\nlog_channel = 2", &[]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid config:\n- `bot_token` is required, set it in the test config or as BOT_TOKEN"
+        );
     }
 
-    fn check_config(&self) -> Vec<&str> {
-        let mut missing = Vec::new();
+    #[test]
+    fn rejects_blank_required_keys() {
+        let error = Config::from_toml("bot_token = \"  \"\nbot_owner = 1\nlog_channel = 2", &[])
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("`bot_token` in the test config can't be empty"));
 
-        if self.exchange_api_key.is_empty() {
-            missing.push("Exchange API Key");
-        } else if self.imgur_client_id.is_empty() {
-            missing.push("Imgur Client ID");
-        } else if self.imgur_client_secret.is_empty() {
-            missing.push("Imgur Client Secret");
-        } else if self.reddit_client_id.is_empty() {
-            missing.push("Reddit Client ID");
-        } else if self.reddit_client_secret.is_empty() {
-            missing.push("Reddit Client Secret");
-        } else if self.reddit_refresh_token.is_empty() {
-            missing.push("Reddit Refresh Token");
+        let error = Config::from_toml(REQUIRED, &[("BOT_TOKEN", "")]).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("environment variable BOT_TOKEN can't be empty"));
+    }
+
+    #[test]
+    fn reports_all_invalid_values_at_once() {
+        let error = config(
+            r#"
+            default_prefix = "$"
+            exchange_cache_ttl = -1
+            status_interval_min = 10
+            status_interval_max = 5
+            backup_retention = 0
+            intents = ["guild_messages", "nope"]
+            dev_channels = [1, "two"]
+            "#,
+        )
+        .unwrap_err()
+        .to_string();
+
+        for message in [
+            "`default_prefix` can't be empty, contain whitespace or start with `$`",
+            "`exchange_cache_ttl` can't be negative",
+            "`status_interval_max` can't be smaller than status_interval_min",
+            "`backup_retention` has to be at least 1",
+            "`intents`: `nope` is not a gateway intent",
+            "`dev_channels` in the test config: `two` is not valid",
+        ] {
+            assert!(error.contains(message), "{message} missing in {error}");
         }
-        missing
+    }
+
+    #[test]
+    fn enables_integrations_with_their_credentials() {
+        let config = config("imgur_client_id = \"imgur\"\nreddit_client_id = \"reddit\"").unwrap();
+
+        assert!(config.is_enabled(Integration::Imgur));
+        assert!(!config.is_enabled(Integration::Exchange));
+        assert_eq!(
+            config.missing_credentials(Integration::Reddit),
+            ["reddit_client_secret", "reddit_refresh_token"]
+        );
     }
 }
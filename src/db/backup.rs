@@ -0,0 +1,486 @@
+//! Snapshots of the database, written on a schedule or on demand, and
+//! restoring them from the command line.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+#[cfg(feature = "sqlite")]
+use {
+    sqlx::{sqlite::SqliteConnectOptions, Connection, SqliteConnection},
+    std::str::FromStr,
+};
+
+use super::repositories::Database;
+#[cfg(feature = "sqlite")]
+use super::sqlite::MIGRATOR;
+use crate::config::Config;
+
+/// The prefix of the file names of backups. Only files with this prefix are
+/// deleted by the retention rules.
+const BACKUP_PREFIX: &str = "hifumi-";
+const BACKUP_EXTENSION: &str = ".db";
+
+/// Writes a snapshot of the database to `backup_dir` and deletes the
+/// backups exceeding `backup_retention`, oldest first.
+///
+/// # Returns
+/// The path of the new backup.
+///
+/// # Errors
+/// * If the backend doesn't support backups, or the snapshot can't be
+///   written.
+pub async fn create_backup(db: &dyn Database, config: &Config) -> Result<PathBuf> {
+    let dir = Path::new(&config.backup_dir);
+    fs::create_dir_all(dir)
+        .map_err(|e| anyhow!("Failed to create backup directory {}: {e}", dir.display()))?;
+
+    let timestamp = Utc::now().format("%Y%m%d-%H%M%S");
+    let path = dir.join(format!("{BACKUP_PREFIX}{timestamp}{BACKUP_EXTENSION}"));
+
+    if path.exists() {
+        bail!("The backup {} already exists", path.display());
+    }
+
+    db.backup_to(&path).await?;
+
+    let deleted = prune_backups(dir, config.backup_retention)?;
+    if deleted > 0 {
+        debug!("Deleted {deleted} old backups");
+    }
+
+    Ok(path)
+}
+
+/// Deletes all but the newest `keep` backups in the directory. The file names
+/// contain the time they were taken at, so sorting them sorts them by age.
+///
+/// # Returns
+/// The number of deleted backups.
+fn prune_backups(dir: &Path, keep: usize) -> Result<usize> {
+    let mut backups = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with(BACKUP_PREFIX) && name.ends_with(BACKUP_EXTENSION)
+                })
+        })
+        .collect::<Vec<_>>();
+
+    backups.sort();
+
+    let excess = backups.len().saturating_sub(keep);
+    for path in &backups[..excess] {
+        fs::remove_file(path)
+            .map_err(|e| anyhow!("Failed to delete old backup {}: {e}", path.display()))?;
+    }
+
+    Ok(excess)
+}
+
+/// Replaces the SQLite database at the URL with the snapshot. The snapshot
+/// has to pass an integrity check and may only contain migrations this build
+/// knows, newer ones are refused. Older snapshots are migrated on the next
+/// start. A snapshot of the replaced database is kept next to it with a
+/// `.pre-restore` suffix.
+///
+/// The bot must not be running while restoring.
+///
+/// # Returns
+/// The schema version of the snapshot.
+///
+/// # Errors
+/// * If the URL isn't a SQLite URL, or the snapshot is invalid or can't be
+///   copied.
+#[cfg(feature = "sqlite")]
+pub async fn restore(url: &str, snapshot: &Path) -> Result<i64> {
+    if !url.starts_with("sqlite:") {
+        bail!("Only SQLite databases can be restored, DATABASE_URL isn't a SQLite URL");
+    }
+    if !snapshot.is_file() {
+        bail!("The snapshot {} doesn't exist", snapshot.display());
+    }
+
+    let target = SqliteConnectOptions::from_str(url)?
+        .get_filename()
+        .to_path_buf();
+
+    let mut conn = SqliteConnection::connect_with(
+        &SqliteConnectOptions::new()
+            .filename(snapshot)
+            .read_only(true),
+    )
+    .await?;
+
+    let integrity: String = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_one(&mut conn)
+        .await
+        .map_err(|e| anyhow!("The snapshot isn't a valid SQLite database: {e}"))?;
+    if integrity != "ok" {
+        bail!("The snapshot is corrupt: {integrity}");
+    }
+
+    let applied: Vec<(i64, Vec<u8>, bool)> =
+        sqlx::query_as("SELECT version, checksum, success FROM _sqlx_migrations ORDER BY version")
+            .fetch_all(&mut conn)
+            .await
+            .map_err(|_| anyhow!("The snapshot has no schema version, it isn't a bot database"))?;
+
+    conn.close().await?;
+
+    let latest = MIGRATOR
+        .iter()
+        .map(|migration| migration.version)
+        .max()
+        .unwrap_or_default();
+
+    for (version, checksum, success) in &applied {
+        let Some(known) = MIGRATOR.iter().find(|m| m.version == *version) else {
+            bail!(
+                "The snapshot has schema version {version}, but this build only knows versions up to {latest}"
+            );
+        };
+        if !success {
+            bail!("Migration {version} failed in the snapshot");
+        }
+        if *known.checksum != **checksum {
+            bail!("Migration {version} of the snapshot differs from the one of this build");
+        }
+    }
+
+    let version = applied
+        .last()
+        .map(|(version, ..)| *version)
+        .ok_or_else(|| anyhow!("The snapshot has no migrations applied"))?;
+
+    let with_suffix = |suffix: &str| {
+        let mut path = target.clone().into_os_string();
+        path.push(suffix);
+        PathBuf::from(path)
+    };
+
+    // Snapshot instead of copying the current database, so changes that are
+    // still in its write-ahead log are kept as well.
+    if target.exists() {
+        let previous = with_suffix(".pre-restore");
+        if previous.exists() {
+            fs::remove_file(&previous)?;
+        }
+
+        let previous = previous
+            .to_str()
+            .ok_or_else(|| anyhow!("The path {} isn't valid UTF-8", previous.display()))?
+            .to_string();
+        let mut conn =
+            SqliteConnection::connect_with(&SqliteConnectOptions::new().filename(&target)).await?;
+        sqlx::query("VACUUM INTO ?")
+            .bind(previous)
+            .execute(&mut conn)
+            .await?;
+        conn.close().await?;
+    }
+
+    // Copy next to the database first, so the swap itself is a single rename.
+    let staging = with_suffix(".restoring");
+    fs::copy(snapshot, &staging)?;
+    fs::rename(&staging, &target)?;
+
+    for suffix in ["-wal", "-shm"] {
+        let path = with_suffix(suffix);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+
+    Ok(version)
+}
+
+#[cfg(not(feature = "sqlite"))]
+pub async fn restore(_url: &str, _snapshot: &Path) -> Result<i64> {
+    bail!("Restoring is only supported with SQLite, build with the `sqlite` feature")
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+    use crate::db::sqlite::SqliteDatabase;
+
+    /// A directory that only exists for a single test.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = env::temp_dir().join(format!("hifumi-backup-{name}-{}", process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        fn file(&self, name: &str) -> PathBuf {
+            self.0.join(name)
+        }
+
+        fn url(&self, name: &str) -> String {
+            format!("sqlite://{}", self.file(name).display())
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Creates a migrated database at the path holding a single error with
+    /// the given command.
+    async fn create_database(path: &Path, command: &str) {
+        let mut conn = SqliteConnection::connect_with(
+            &SqliteConnectOptions::new()
+                .filename(path)
+                .create_if_missing(true),
+        )
+        .await
+        .unwrap();
+        MIGRATOR.run(&mut conn).await.unwrap();
+        sqlx::query("INSERT INTO error_log (channel, user, command) VALUES ('1', '2', ?)")
+            .bind(command)
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        conn.close().await.unwrap();
+    }
+
+    async fn execute(path: &Path, sql: &str) {
+        let mut conn = SqliteConnection::connect_with(&SqliteConnectOptions::new().filename(path))
+            .await
+            .unwrap();
+        sqlx::query(sql).execute(&mut conn).await.unwrap();
+        conn.close().await.unwrap();
+    }
+
+    async fn stored_commands(path: &Path) -> Vec<String> {
+        let mut conn = SqliteConnection::connect_with(&SqliteConnectOptions::new().filename(path))
+            .await
+            .unwrap();
+        let commands = sqlx::query_scalar("SELECT command FROM error_log ORDER BY id")
+            .fetch_all(&mut conn)
+            .await
+            .unwrap();
+        conn.close().await.unwrap();
+        commands
+    }
+
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    fn latest_version() -> i64 {
+        MIGRATOR
+            .iter()
+            .map(|migration| migration.version)
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn prune_backups_keeps_the_newest() {
+        let dir = TempDir::new("prune");
+        for name in [
+            "hifumi-20240103-000000.db",
+            "hifumi-20240101-000000.db",
+            "hifumi-20240102-120000.db",
+            "hifumi-20240102-000000.db",
+            "hifumi-notes.txt",
+            "other.db",
+        ] {
+            fs::write(dir.file(name), "").unwrap();
+        }
+
+        assert_eq!(prune_backups(&dir.0, 2).unwrap(), 2);
+        assert_eq!(
+            file_names(&dir.0),
+            [
+                "hifumi-20240102-120000.db",
+                "hifumi-20240103-000000.db",
+                "hifumi-notes.txt",
+                "other.db"
+            ]
+        );
+
+        assert_eq!(prune_backups(&dir.0, 5).unwrap(), 0);
+        assert_eq!(file_names(&dir.0).len(), 4);
+    }
+
+    #[tokio::test]
+    async fn create_backup_writes_a_snapshot_and_prunes() {
+        let dir = TempDir::new("create");
+        let backups = dir.file("backups");
+        fs::create_dir_all(&backups).unwrap();
+        for name in ["hifumi-20000101-000000.db", "hifumi-20000102-000000.db"] {
+            fs::write(backups.join(name), "").unwrap();
+        }
+
+        let db = SqliteDatabase::connect(&dir.url("bot.db")).await.unwrap();
+        let toml = format!(
+            "bot_token = \"token\"\nbot_owner = 1\nlog_channel = 1\n\
+            backup_dir = \"{}\"\nbackup_retention = 2",
+            backups.display()
+        );
+        let config = Config::from_toml(&toml, &[]).unwrap();
+
+        let path = create_backup(&db, &config).await.unwrap();
+
+        assert_eq!(
+            file_names(&backups),
+            [
+                "hifumi-20000102-000000.db".to_string(),
+                path.file_name().unwrap().to_str().unwrap().to_string()
+            ]
+        );
+        assert!(stored_commands(&path).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn restore_replaces_the_database() {
+        let dir = TempDir::new("restore");
+        let (target, snapshot) = (dir.file("bot.db"), dir.file("snapshot.db"));
+        create_database(&target, "current").await;
+        create_database(&snapshot, "snapshot").await;
+        fs::write(dir.file("bot.db-wal"), "").unwrap();
+        fs::write(dir.file("bot.db-shm"), "").unwrap();
+
+        let version = restore(&dir.url("bot.db"), &snapshot).await.unwrap();
+
+        assert_eq!(version, latest_version());
+        assert_eq!(stored_commands(&target).await, ["snapshot"]);
+        assert_eq!(
+            stored_commands(&dir.file("bot.db.pre-restore")).await,
+            ["current"]
+        );
+        assert_eq!(
+            file_names(&dir.0),
+            ["bot.db", "bot.db.pre-restore", "snapshot.db"]
+        );
+    }
+
+    #[tokio::test]
+    async fn restore_accepts_older_schemas() {
+        let dir = TempDir::new("restore-older");
+        let snapshot = dir.file("snapshot.db");
+        create_database(&snapshot, "snapshot").await;
+        execute(
+            &snapshot,
+            &format!(
+                "DELETE FROM _sqlx_migrations WHERE version = {}",
+                latest_version()
+            ),
+        )
+        .await;
+
+        let version = restore(&dir.url("bot.db"), &snapshot).await.unwrap();
+
+        assert_eq!(version, latest_version() - 1);
+        assert_eq!(stored_commands(&dir.file("bot.db")).await, ["snapshot"]);
+    }
+
+    /// Restores the snapshot over the database created by the test and
+    /// checks that it was refused with the error and the database was left
+    /// alone.
+    async fn assert_refused(dir: &TempDir, snapshot: &Path, error: &str) {
+        let target = dir.file("bot.db");
+        let e = restore(&dir.url("bot.db"), snapshot).await.unwrap_err();
+
+        assert!(e.to_string().contains(error), "{e}");
+        assert_eq!(stored_commands(&target).await, ["current"]);
+        assert!(!dir.file("bot.db.pre-restore").exists());
+        assert!(!dir.file("bot.db.restoring").exists());
+    }
+
+    #[tokio::test]
+    async fn restore_refuses_corrupt_snapshots() {
+        let dir = TempDir::new("restore-corrupt");
+        let snapshot = dir.file("snapshot.db");
+        create_database(&dir.file("bot.db"), "current").await;
+
+        fs::write(&snapshot, "not a database").unwrap();
+        assert_refused(&dir, &snapshot, "isn't a valid SQLite database").await;
+
+        fs::remove_file(&snapshot).unwrap();
+        create_database(&snapshot, "snapshot").await;
+        let contents = fs::read(&snapshot).unwrap();
+        fs::write(&snapshot, &contents[..contents.len() / 2]).unwrap();
+        assert!(restore(&dir.url("bot.db"), &snapshot).await.is_err());
+        assert_eq!(stored_commands(&dir.file("bot.db")).await, ["current"]);
+        assert!(!dir.file("bot.db.pre-restore").exists());
+    }
+
+    #[tokio::test]
+    async fn restore_refuses_unknown_schemas() {
+        let dir = TempDir::new("restore-schema");
+        let snapshot = dir.file("snapshot.db");
+        create_database(&dir.file("bot.db"), "current").await;
+
+        let mut conn = SqliteConnection::connect_with(
+            &SqliteConnectOptions::new()
+                .filename(&snapshot)
+                .create_if_missing(true),
+        )
+        .await
+        .unwrap();
+        sqlx::query("CREATE TABLE error_log (id INTEGER)")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        conn.close().await.unwrap();
+        assert_refused(&dir, &snapshot, "has no schema version").await;
+
+        fs::remove_file(&snapshot).unwrap();
+        create_database(&snapshot, "snapshot").await;
+        execute(
+            &snapshot,
+            "INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time)
+            VALUES (9999, 'future', TRUE, X'00', 0)",
+        )
+        .await;
+        assert_refused(&dir, &snapshot, "has schema version 9999").await;
+
+        fs::remove_file(&snapshot).unwrap();
+        create_database(&snapshot, "snapshot").await;
+        execute(
+            &snapshot,
+            "UPDATE _sqlx_migrations SET checksum = X'00' WHERE version = 1",
+        )
+        .await;
+        assert_refused(&dir, &snapshot, "Migration 1 of the snapshot differs").await;
+    }
+
+    #[tokio::test]
+    async fn restore_only_supports_sqlite() {
+        let dir = TempDir::new("restore-url");
+        let snapshot = dir.file("snapshot.db");
+        create_database(&snapshot, "snapshot").await;
+
+        let e = restore("postgres://localhost/hifumi", &snapshot)
+            .await
+            .unwrap_err();
+        assert!(e.to_string().contains("isn't a SQLite URL"));
+
+        let e = restore(&dir.url("bot.db"), &dir.file("missing.db"))
+            .await
+            .unwrap_err();
+        assert!(e.to_string().contains("doesn't exist"));
+    }
+}
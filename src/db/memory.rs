@@ -0,0 +1,616 @@
+//! An in-memory implementation of all repositories, so logic built on them
+//! can be tested without a database. It mirrors the behaviour of the SQL
+//! backends, such as the orderings and the uniqueness constraints.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use anyhow::{bail, Result};
+use rand::{seq::SliceRandom, thread_rng};
+use serenity::async_trait;
+
+use super::{
+    models::{
+        AiCommandAlias, AiReactions, BotContent, ErrorLog, HelpMessage, Leet, Prefix, RedditPost,
+        Status, StatusType,
+    },
+    repositories::{
+        BackupRepository, ContentRepository, ErrorRepository, ExchangeRepository,
+        GuildSettingsRepository, PrefixRepository, ReactionRepository, RedditRepository,
+        StatusRepository,
+    },
+};
+
+/// When a subreddit was last requested and when its posts were last fetched.
+struct Subreddit {
+    requested_at: i64,
+    fetched_at: Option<i64>,
+}
+
+#[derive(Default)]
+struct Tables {
+    last_id: i64,
+    errors: Vec<ErrorLog>,
+    prefixes: Vec<Prefix>,
+    statuses: Vec<Status>,
+    reactions: Vec<AiReactions>,
+    aliases: Vec<AiCommandAlias>,
+    help_messages: Vec<HelpMessage>,
+    leet: Vec<Leet>,
+    reddit_posts: Vec<RedditPost>,
+    subreddits: HashMap<String, Subreddit>,
+    guild_settings: HashMap<String, bool>,
+    rates: HashMap<(String, String), (f64, i64)>,
+    currencies: BTreeMap<String, String>,
+}
+
+impl Tables {
+    /// Returns a new Id. Ids are shared by all tables, which is fine as long
+    /// as they are unique within each of them.
+    fn next_id(&mut self) -> i64 {
+        self.last_id += 1;
+        self.last_id
+    }
+}
+
+/// Keeps all rows in memory, they are gone once it is dropped.
+#[derive(Default)]
+pub struct MemoryDatabase {
+    tables: Mutex<Tables>,
+}
+
+impl MemoryDatabase {
+    fn tables(&self) -> MutexGuard<'_, Tables> {
+        self.tables.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[async_trait]
+impl ErrorRepository for MemoryDatabase {
+    async fn insert_error(&self, error: &ErrorLog) -> Result<i64> {
+        let mut tables = self.tables();
+        let id = tables.next_id();
+
+        tables.errors.push(ErrorLog {
+            id,
+            ..error.clone()
+        });
+
+        Ok(id)
+    }
+
+    async fn list_errors(&self, limit: i64) -> Result<Vec<ErrorLog>> {
+        let limit = usize::try_from(limit).unwrap_or_default();
+
+        Ok(self
+            .tables()
+            .errors
+            .iter()
+            .rev()
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_error(&self, id: i64) -> Result<Option<ErrorLog>> {
+        Ok(self
+            .tables()
+            .errors
+            .iter()
+            .find(|error| error.id == id)
+            .cloned())
+    }
+
+    async fn search_errors(&self, query: &str, limit: i64) -> Result<Vec<ErrorLog>> {
+        let limit = usize::try_from(limit).unwrap_or_default();
+        let matches = |field: &Option<String>| field.as_deref().is_some_and(|f| f.contains(query));
+
+        Ok(self
+            .tables()
+            .errors
+            .iter()
+            .rev()
+            .filter(|error| {
+                matches(&error.error) || matches(&error.command) || matches(&error.stack)
+            })
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    async fn delete_errors_before(&self, timestamp: i64) -> Result<u64> {
+        let mut tables = self.tables();
+        let before = tables.errors.len();
+
+        tables
+            .errors
+            .retain(|error| error.timestamp.is_none_or(|ts| ts >= timestamp));
+
+        Ok((before - tables.errors.len()) as u64)
+    }
+}
+
+#[async_trait]
+impl PrefixRepository for MemoryDatabase {
+    async fn list_prefixes(&self) -> Result<Vec<Prefix>> {
+        Ok(self.tables().prefixes.clone())
+    }
+
+    async fn add_prefix(&self, server_id: &str, prefix: &str) -> Result<()> {
+        let mut tables = self.tables();
+
+        if tables
+            .prefixes
+            .iter()
+            .any(|row| row.server_id == server_id && row.prefix == prefix)
+        {
+            return Ok(());
+        }
+
+        let id = tables.next_id();
+        tables.prefixes.push(Prefix {
+            id,
+            server_id: server_id.to_string(),
+            prefix: prefix.to_string(),
+        });
+
+        Ok(())
+    }
+
+    async fn replace_prefixes(&self, server_id: &str, prefix: &str) -> Result<()> {
+        self.tables()
+            .prefixes
+            .retain(|row| row.server_id != server_id);
+
+        self.add_prefix(server_id, prefix).await
+    }
+
+    async fn remove_prefix(&self, server_id: &str, prefix: &str) -> Result<bool> {
+        let mut tables = self.tables();
+        let before = tables.prefixes.len();
+
+        tables
+            .prefixes
+            .retain(|row| row.server_id != server_id || row.prefix != prefix);
+
+        Ok(tables.prefixes.len() < before)
+    }
+}
+
+#[async_trait]
+impl StatusRepository for MemoryDatabase {
+    async fn list_statuses(&self) -> Result<Vec<Status>> {
+        Ok(self.tables().statuses.clone())
+    }
+
+    async fn add_status(&self, status_type: &StatusType, text: &str) -> Result<i64> {
+        let mut tables = self.tables();
+        let id = tables.next_id();
+
+        tables.statuses.push(Status {
+            id,
+            r#type: status_type.clone(),
+            status: text.to_string(),
+            weight: 1,
+            active_days: None,
+            starts_at: None,
+            ends_at: None,
+        });
+
+        Ok(id)
+    }
+
+    async fn remove_status(&self, id: i64) -> Result<bool> {
+        let mut tables = self.tables();
+        let before = tables.statuses.len();
+
+        tables.statuses.retain(|status| status.id != id);
+
+        Ok(tables.statuses.len() < before)
+    }
+
+    async fn set_status_weight(&self, id: i64, weight: i64) -> Result<bool> {
+        let mut tables = self.tables();
+        let Some(status) = tables.statuses.iter_mut().find(|status| status.id == id) else {
+            return Ok(false);
+        };
+
+        status.weight = weight;
+
+        Ok(true)
+    }
+
+    async fn set_status_schedule(
+        &self,
+        id: i64,
+        active_days: Option<&str>,
+        starts_at: Option<i64>,
+        ends_at: Option<i64>,
+    ) -> Result<bool> {
+        let mut tables = self.tables();
+        let Some(status) = tables.statuses.iter_mut().find(|status| status.id == id) else {
+            return Ok(false);
+        };
+
+        status.active_days = active_days.map(ToString::to_string);
+        status.starts_at = starts_at;
+        status.ends_at = ends_at;
+
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl ReactionRepository for MemoryDatabase {
+    async fn list_reactions(&self, command: Option<&str>) -> Result<Vec<AiReactions>> {
+        let mut reactions = self
+            .tables()
+            .reactions
+            .iter()
+            .filter(|row| command.is_none_or(|command| row.command == command))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        reactions.sort_by(|a, b| (&a.command, a.id).cmp(&(&b.command, b.id)));
+
+        Ok(reactions)
+    }
+
+    async fn add_reaction(&self, command: &str, reaction: &str) -> Result<i64> {
+        let mut tables = self.tables();
+        let id = tables.next_id();
+
+        tables.reactions.push(AiReactions {
+            id,
+            command: command.to_string(),
+            reaction: reaction.to_string(),
+        });
+
+        Ok(id)
+    }
+
+    async fn remove_reaction(&self, id: i64) -> Result<bool> {
+        let mut tables = self.tables();
+        let before = tables.reactions.len();
+
+        tables.reactions.retain(|row| row.id != id);
+
+        Ok(tables.reactions.len() < before)
+    }
+
+    async fn resolve_alias(&self, alias: &str) -> Result<Option<String>> {
+        Ok(self
+            .tables()
+            .aliases
+            .iter()
+            .find(|row| row.alias == alias)
+            .map(|row| row.command.clone()))
+    }
+
+    async fn list_aliases(&self) -> Result<Vec<AiCommandAlias>> {
+        let mut aliases = self.tables().aliases.clone();
+
+        aliases.sort_by(|a, b| (&a.command, &a.alias).cmp(&(&b.command, &b.alias)));
+
+        Ok(aliases)
+    }
+
+    async fn set_alias(&self, alias: &str, command: &str) -> Result<()> {
+        let mut tables = self.tables();
+
+        if let Some(row) = tables.aliases.iter_mut().find(|row| row.alias == alias) {
+            row.command = command.to_string();
+            return Ok(());
+        }
+
+        let id = tables.next_id();
+        tables.aliases.push(AiCommandAlias {
+            id,
+            command: command.to_string(),
+            alias: alias.to_string(),
+        });
+
+        Ok(())
+    }
+
+    async fn remove_alias(&self, alias: &str) -> Result<bool> {
+        let mut tables = self.tables();
+        let before = tables.aliases.len();
+
+        tables.aliases.retain(|row| row.alias != alias);
+
+        Ok(tables.aliases.len() < before)
+    }
+}
+
+#[async_trait]
+impl ContentRepository for MemoryDatabase {
+    async fn list_help_messages(&self) -> Result<Vec<HelpMessage>> {
+        Ok(self.tables().help_messages.clone())
+    }
+
+    async fn list_leet(&self) -> Result<Vec<Leet>> {
+        Ok(self.tables().leet.clone())
+    }
+
+    async fn replace_content(&self, content: &BotContent) -> Result<()> {
+        let mut tables = self.tables();
+
+        if let Some(statuses) = &content.statuses {
+            tables.statuses.clone_from(statuses);
+        }
+        if let Some(help_messages) = &content.help_messages {
+            tables.help_messages.clone_from(help_messages);
+        }
+        if let Some(leet) = &content.leet {
+            tables.leet.clone_from(leet);
+        }
+        if let Some(reactions) = &content.ai_reactions {
+            tables.reactions.clone_from(reactions);
+        }
+        if let Some(aliases) = &content.ai_command_aliases {
+            tables.aliases.clone_from(aliases);
+        }
+        if let Some(prefixes) = &content.prefixes {
+            tables.prefixes.clone_from(prefixes);
+        }
+
+        // Imported rows keep their Ids, so new rows have to start after them.
+        let imported = [
+            tables.statuses.iter().map(|row| row.id).max(),
+            tables.help_messages.iter().map(|row| row.id).max(),
+            tables.leet.iter().map(|row| row.id).max(),
+            tables.reactions.iter().map(|row| row.id).max(),
+            tables.aliases.iter().map(|row| row.id).max(),
+            tables.prefixes.iter().map(|row| row.id).max(),
+        ];
+        tables.last_id = imported
+            .into_iter()
+            .flatten()
+            .fold(tables.last_id, i64::max);
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RedditRepository for MemoryDatabase {
+    async fn replace_reddit_posts(
+        &self,
+        subreddit: &str,
+        posts: &[RedditPost],
+        fetched_at: i64,
+    ) -> Result<()> {
+        let mut tables = self.tables();
+
+        tables
+            .reddit_posts
+            .retain(|post| post.subreddit != subreddit);
+
+        for post in posts {
+            if tables
+                .reddit_posts
+                .iter()
+                .any(|row| row.subreddit == subreddit && row.url == post.url)
+            {
+                continue;
+            }
+
+            let id = tables.next_id();
+            tables.reddit_posts.push(RedditPost {
+                id,
+                subreddit: subreddit.to_string(),
+                ..post.clone()
+            });
+        }
+
+        tables
+            .subreddits
+            .entry(subreddit.to_string())
+            .or_insert(Subreddit {
+                requested_at: fetched_at,
+                fetched_at: None,
+            })
+            .fetched_at = Some(fetched_at);
+
+        Ok(())
+    }
+
+    async fn random_reddit_post(
+        &self,
+        subreddit: &str,
+        allow_nsfw: bool,
+    ) -> Result<Option<RedditPost>> {
+        let tables = self.tables();
+        let posts = tables
+            .reddit_posts
+            .iter()
+            .filter(|post| post.subreddit == subreddit)
+            .collect::<Vec<_>>();
+        let preferred = posts
+            .iter()
+            .filter(|post| allow_nsfw || !post.over_18)
+            .copied()
+            .collect::<Vec<_>>();
+
+        let candidates = if preferred.is_empty() {
+            posts
+        } else {
+            preferred
+        };
+
+        Ok(candidates
+            .choose(&mut thread_rng())
+            .map(|post| (*post).clone()))
+    }
+
+    async fn touch_subreddit(&self, subreddit: &str, requested_at: i64) -> Result<()> {
+        self.tables()
+            .subreddits
+            .entry(subreddit.to_string())
+            .or_insert(Subreddit {
+                requested_at,
+                fetched_at: None,
+            })
+            .requested_at = requested_at;
+
+        Ok(())
+    }
+
+    async fn subreddit_fetched_at(&self, subreddit: &str) -> Result<Option<i64>> {
+        Ok(self
+            .tables()
+            .subreddits
+            .get(subreddit)
+            .and_then(|subreddit| subreddit.fetched_at))
+    }
+
+    async fn cached_subreddits(&self, requested_after: i64) -> Result<Vec<String>> {
+        Ok(self
+            .tables()
+            .subreddits
+            .iter()
+            .filter(|(_, subreddit)| {
+                subreddit.fetched_at.is_some() && subreddit.requested_at > requested_after
+            })
+            .map(|(name, _)| name.clone())
+            .collect())
+    }
+
+    async fn expire_subreddits(&self, requested_before: i64) -> Result<u64> {
+        let mut tables = self.tables();
+        let before = tables.subreddits.len();
+
+        tables
+            .subreddits
+            .retain(|_, subreddit| subreddit.requested_at >= requested_before);
+
+        let Tables {
+            subreddits,
+            reddit_posts,
+            ..
+        } = &mut *tables;
+        reddit_posts.retain(|post| subreddits.contains_key(&post.subreddit));
+
+        Ok((before - tables.subreddits.len()) as u64)
+    }
+}
+
+#[async_trait]
+impl GuildSettingsRepository for MemoryDatabase {
+    async fn nsfw_enabled(&self, server_id: &str) -> Result<Option<bool>> {
+        Ok(self.tables().guild_settings.get(server_id).copied())
+    }
+
+    async fn set_nsfw_enabled(&self, server_id: &str, enabled: bool) -> Result<()> {
+        self.tables()
+            .guild_settings
+            .insert(server_id.to_string(), enabled);
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ExchangeRepository for MemoryDatabase {
+    async fn cached_rate(&self, base: &str, target: &str, newer_than: i64) -> Result<Option<f64>> {
+        Ok(self
+            .tables()
+            .rates
+            .get(&(base.to_string(), target.to_string()))
+            .filter(|(_, fetched_at)| *fetched_at > newer_than)
+            .map(|(rate, _)| *rate))
+    }
+
+    async fn store_rates(
+        &self,
+        base: &str,
+        rates: &HashMap<String, f64>,
+        fetched_at: i64,
+    ) -> Result<()> {
+        let mut tables = self.tables();
+
+        for (target, rate) in rates {
+            tables
+                .rates
+                .insert((base.to_string(), target.clone()), (*rate, fetched_at));
+        }
+
+        Ok(())
+    }
+
+    async fn list_currencies(&self) -> Result<Vec<(String, String)>> {
+        Ok(self
+            .tables()
+            .currencies
+            .iter()
+            .map(|(code, name)| (code.clone(), name.clone()))
+            .collect())
+    }
+
+    async fn store_currencies(&self, currencies: &[(String, String)]) -> Result<()> {
+        self.tables().currencies.extend(currencies.iter().cloned());
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl BackupRepository for MemoryDatabase {
+    fn supports_backups(&self) -> bool {
+        false
+    }
+
+    async fn backup_to(&self, _path: &Path) -> Result<()> {
+        bail!("The in-memory database can't be backed up")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::repository_tests;
+
+    #[tokio::test]
+    async fn errors() {
+        repository_tests::errors(&MemoryDatabase::default()).await;
+    }
+
+    #[tokio::test]
+    async fn prefixes() {
+        repository_tests::prefixes(&MemoryDatabase::default()).await;
+    }
+
+    #[tokio::test]
+    async fn statuses() {
+        repository_tests::statuses(&MemoryDatabase::default()).await;
+    }
+
+    #[tokio::test]
+    async fn reactions() {
+        repository_tests::reactions(&MemoryDatabase::default()).await;
+    }
+
+    #[tokio::test]
+    async fn content() {
+        repository_tests::content(&MemoryDatabase::default()).await;
+    }
+
+    #[tokio::test]
+    async fn reddit() {
+        repository_tests::reddit(&MemoryDatabase::default()).await;
+    }
+
+    #[tokio::test]
+    async fn guild_settings() {
+        repository_tests::guild_settings(&MemoryDatabase::default()).await;
+    }
+
+    #[tokio::test]
+    async fn exchange() {
+        repository_tests::exchange(&MemoryDatabase::default()).await;
+    }
+}
@@ -1 +1,52 @@
-pub mod models;
\ No newline at end of file
+use anyhow::{bail, Result};
+
+use self::repositories::Database;
+
+pub mod backup;
+#[cfg(test)]
+pub mod memory;
+pub mod models;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod repositories;
+#[cfg(test)]
+mod repository_tests;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+/// Escapes `%`, `_` and `\` so a `LIKE` pattern using `ESCAPE '\'` matches
+/// the text literally.
+pub(crate) fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Connects to the database at the URL, picking the backend by its scheme,
+/// and runs all pending migrations.
+///
+/// # Errors
+/// * If the scheme is unknown or its backend wasn't compiled in.
+/// * If connecting or migrating fails.
+pub async fn connect(url: &str) -> Result<Box<dyn Database>> {
+    let scheme = url.split(':').next().unwrap_or_default();
+
+    match scheme {
+        #[cfg(feature = "sqlite")]
+        "sqlite" => Ok(Box::new(sqlite::SqliteDatabase::connect(url).await?)),
+        #[cfg(feature = "postgres")]
+        "postgres" | "postgresql" => Ok(Box::new(postgres::PostgresDatabase::connect(url).await?)),
+        #[cfg(not(feature = "sqlite"))]
+        "sqlite" => bail!("DATABASE_URL is a SQLite URL, but the `sqlite` feature is disabled"),
+        #[cfg(not(feature = "postgres"))]
+        "postgres" | "postgresql" => {
+            bail!("DATABASE_URL is a Postgres URL, but the `postgres` feature is disabled")
+        }
+        _ => bail!("DATABASE_URL has the unsupported scheme `{scheme}`"),
+    }
+}
@@ -1,7 +1,8 @@
+use chrono::{DateTime, Datelike, Utc, Weekday};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
-#[derive(Serialize, Deserialize, FromRow)]
+#[derive(Serialize, Deserialize, Clone, FromRow)]
 pub struct ErrorLog {
     pub id: i64,
     pub server: Option<String>,
@@ -13,31 +14,33 @@ pub struct ErrorLog {
     pub log: Option<String>,
     pub error: Option<String>,
 }
-#[derive(Serialize, Deserialize, FromRow)]
+#[derive(Serialize, Deserialize, Clone, FromRow)]
 pub struct HelpMessage {
     pub id: i64,
     pub cmd: String,
     pub desc: String,
 }
-#[derive(Serialize, Deserialize, FromRow)]
+#[derive(Serialize, Deserialize, Clone, FromRow)]
 pub struct Leet {
     pub id: i64,
     pub source: char,
     pub translated: String,
 }
-#[derive(Serialize, Deserialize, FromRow)]
+#[derive(Serialize, Deserialize, Clone, FromRow)]
 pub struct AiCommandAlias {
     pub id: i64,
     pub command: String,
     pub alias: String,
 }
-#[derive(Serialize, Deserialize, FromRow)]
+#[derive(Serialize, Deserialize, Clone, FromRow)]
 pub struct AiReactions {
     pub id: i64,
     pub command: String,
     pub reaction: String,
 }
-#[derive(Serialize, Deserialize, FromRow)]
+/// A single prefix of a guild. Guilds can have several prefixes, so there is
+/// one row per (`server_id`, `prefix`) pair.
+#[derive(Serialize, Deserialize, Clone, FromRow)]
 pub struct Prefix {
     pub id: i64,
     #[serde(rename = "serverId")]
@@ -45,7 +48,7 @@ pub struct Prefix {
     pub prefix: String,
 }
 
-#[derive(Serialize, Deserialize, FromRow)]
+#[derive(Serialize, Deserialize, Clone, FromRow)]
 pub struct RedditPost {
     pub id: i64,
     pub subreddit: String,
@@ -64,6 +67,19 @@ pub enum StatusType {
     Custom,
 }
 
+impl StatusType {
+    /// The name the status type is stored as in the database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatusType::Watching => "Watching",
+            StatusType::Listening => "Listening",
+            StatusType::Playing => "Playing",
+            StatusType::Competing => "Competing",
+            StatusType::Custom => "Custom",
+        }
+    }
+}
+
 impl From<String> for StatusType {
     fn from(s: String) -> Self {
         match s.to_lowercase().as_str() {
@@ -76,9 +92,75 @@ impl From<String> for StatusType {
     }
 }
 
+/// A status the bot rotates through. The status text can contain template
+/// variables such as `{servers}` or `{uptime}`, which are filled in when the
+/// status is set.
 #[derive(Serialize, Deserialize, Clone, FromRow)]
 pub struct Status {
     pub id: i64,
+    #[sqlx(rename = "type", try_from = "String")]
     pub r#type: StatusType,
     pub status: String,
+    /// How likely this status is to be picked compared to the other ones.
+    #[serde(default = "default_weight")]
+    pub weight: i64,
+    /// Comma separated weekdays (e.g. `sat,sun`) this status is limited to.
+    pub active_days: Option<String>,
+    /// Unix timestamp from which on this status is active.
+    pub starts_at: Option<i64>,
+    /// Unix timestamp until which this status is active.
+    pub ends_at: Option<i64>,
+}
+
+fn default_weight() -> i64 {
+    1
+}
+
+impl Status {
+    /// Checks if the status can be picked at the given time, based on its
+    /// weight, its active days and its start and end dates.
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        if self.weight <= 0 {
+            return false;
+        }
+
+        if self
+            .starts_at
+            .is_some_and(|starts_at| now.timestamp() < starts_at)
+            || self
+                .ends_at
+                .is_some_and(|ends_at| now.timestamp() > ends_at)
+        {
+            return false;
+        }
+
+        match &self.active_days {
+            Some(days) if !days.trim().is_empty() => days
+                .split(',')
+                .filter_map(|day| day.trim().parse::<Weekday>().ok())
+                .any(|day| day == now.weekday()),
+            _ => true,
+        }
+    }
+}
+
+/// The content of the bot that is maintained by hand rather than produced by
+/// its users, as exported to and imported from JSON.
+///
+/// Sections missing from an imported file are left untouched, the other ones
+/// replace the current rows, including their Ids.
+#[derive(Serialize, Deserialize, Default)]
+pub struct BotContent {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub statuses: Option<Vec<Status>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub help_messages: Option<Vec<HelpMessage>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leet: Option<Vec<Leet>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ai_reactions: Option<Vec<AiReactions>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ai_command_aliases: Option<Vec<AiCommandAlias>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefixes: Option<Vec<Prefix>>,
 }
@@ -0,0 +1,833 @@
+use std::{collections::HashMap, path::Path, str::FromStr};
+
+use anyhow::{bail, Result};
+use serenity::async_trait;
+use sqlx::{postgres::PgConnectOptions, PgPool};
+
+use super::{
+    escape_like,
+    models::{
+        AiCommandAlias, AiReactions, BotContent, ErrorLog, HelpMessage, Leet, Prefix, RedditPost,
+        Status, StatusType,
+    },
+    repositories::{
+        BackupRepository, ContentRepository, ErrorRepository, ExchangeRepository,
+        GuildSettingsRepository, PrefixRepository, ReactionRepository, RedditRepository,
+        StatusRepository,
+    },
+};
+
+/// The tables [`BotContent`] is stored in.
+const CONTENT_TABLES: [&str; 6] = [
+    "statuses",
+    "help_messages",
+    "leet",
+    "ai_reactions",
+    "ai_command_aliases",
+    "prefixes",
+];
+
+/// The PostgreSQL implementation of all repositories.
+///
+/// The queries are checked at runtime instead of compile time, so building
+/// the bot doesn't require a running Postgres server.
+pub struct PostgresDatabase {
+    pool: PgPool,
+}
+
+impl PostgresDatabase {
+    /// Connects to the database at the URL and runs all pending migrations.
+    ///
+    /// # Errors
+    /// * If connecting or migrating fails.
+    pub async fn connect(url: &str) -> Result<Self> {
+        Self::connect_with(PgConnectOptions::from_str(url)?).await
+    }
+
+    async fn connect_with(options: PgConnectOptions) -> Result<Self> {
+        let pool = PgPool::connect_with(options).await?;
+
+        sqlx::migrate!("./migrations/postgres").run(&pool).await?;
+
+        Ok(Self { pool })
+    }
+}
+
+#[async_trait]
+impl ErrorRepository for PostgresDatabase {
+    async fn insert_error(&self, error: &ErrorLog) -> Result<i64> {
+        let id = sqlx::query_scalar(
+            "INSERT INTO error_log (server, channel, \"user\", command, stack, timestamp, log, error)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id",
+        )
+        .bind(&error.server)
+        .bind(&error.channel)
+        .bind(&error.user)
+        .bind(&error.command)
+        .bind(&error.stack)
+        .bind(error.timestamp)
+        .bind(&error.log)
+        .bind(&error.error)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    async fn list_errors(&self, limit: i64) -> Result<Vec<ErrorLog>> {
+        let errors = sqlx::query_as("SELECT * FROM error_log ORDER BY id DESC LIMIT $1")
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(errors)
+    }
+
+    async fn get_error(&self, id: i64) -> Result<Option<ErrorLog>> {
+        let error = sqlx::query_as("SELECT * FROM error_log WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(error)
+    }
+
+    async fn search_errors(&self, query: &str, limit: i64) -> Result<Vec<ErrorLog>> {
+        let errors = sqlx::query_as(
+            r"SELECT * FROM error_log
+            WHERE error LIKE '%' || $1 || '%' ESCAPE '\'
+            OR command LIKE '%' || $1 || '%' ESCAPE '\'
+            OR stack LIKE '%' || $1 || '%' ESCAPE '\'
+            ORDER BY id DESC
+            LIMIT $2",
+        )
+        .bind(escape_like(query))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(errors)
+    }
+
+    async fn delete_errors_before(&self, timestamp: i64) -> Result<u64> {
+        let deleted = sqlx::query("DELETE FROM error_log WHERE timestamp < $1")
+            .bind(timestamp)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        Ok(deleted)
+    }
+}
+
+#[async_trait]
+impl PrefixRepository for PostgresDatabase {
+    async fn list_prefixes(&self) -> Result<Vec<Prefix>> {
+        let prefixes = sqlx::query_as("SELECT * FROM prefixes")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(prefixes)
+    }
+
+    async fn add_prefix(&self, server_id: &str, prefix: &str) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO prefixes (server_id, prefix) VALUES ($1, $2)
+            ON CONFLICT DO NOTHING",
+        )
+        .bind(server_id)
+        .bind(prefix)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn replace_prefixes(&self, server_id: &str, prefix: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM prefixes WHERE server_id = $1")
+            .bind(server_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("INSERT INTO prefixes (server_id, prefix) VALUES ($1, $2)")
+            .bind(server_id)
+            .bind(prefix)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn remove_prefix(&self, server_id: &str, prefix: &str) -> Result<bool> {
+        let deleted = sqlx::query("DELETE FROM prefixes WHERE server_id = $1 AND prefix = $2")
+            .bind(server_id)
+            .bind(prefix)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        Ok(deleted > 0)
+    }
+}
+
+#[async_trait]
+impl StatusRepository for PostgresDatabase {
+    async fn list_statuses(&self) -> Result<Vec<Status>> {
+        let statuses = sqlx::query_as("SELECT * FROM statuses")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(statuses)
+    }
+
+    async fn add_status(&self, status_type: &StatusType, text: &str) -> Result<i64> {
+        let id =
+            sqlx::query_scalar("INSERT INTO statuses (type, status) VALUES ($1, $2) RETURNING id")
+                .bind(status_type.as_str())
+                .bind(text)
+                .fetch_one(&self.pool)
+                .await?;
+
+        Ok(id)
+    }
+
+    async fn remove_status(&self, id: i64) -> Result<bool> {
+        let deleted = sqlx::query("DELETE FROM statuses WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        Ok(deleted > 0)
+    }
+
+    async fn set_status_weight(&self, id: i64, weight: i64) -> Result<bool> {
+        let updated = sqlx::query("UPDATE statuses SET weight = $1 WHERE id = $2")
+            .bind(weight)
+            .bind(id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        Ok(updated > 0)
+    }
+
+    async fn set_status_schedule(
+        &self,
+        id: i64,
+        active_days: Option<&str>,
+        starts_at: Option<i64>,
+        ends_at: Option<i64>,
+    ) -> Result<bool> {
+        let updated = sqlx::query(
+            "UPDATE statuses SET active_days = $1, starts_at = $2, ends_at = $3 WHERE id = $4",
+        )
+        .bind(active_days)
+        .bind(starts_at)
+        .bind(ends_at)
+        .bind(id)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(updated > 0)
+    }
+}
+
+#[async_trait]
+impl ReactionRepository for PostgresDatabase {
+    async fn list_reactions(&self, command: Option<&str>) -> Result<Vec<AiReactions>> {
+        let reactions = match command {
+            Some(command) => {
+                sqlx::query_as("SELECT * FROM ai_reactions WHERE command = $1 ORDER BY id")
+                    .bind(command)
+                    .fetch_all(&self.pool)
+                    .await?
+            }
+            None => {
+                sqlx::query_as("SELECT * FROM ai_reactions ORDER BY command, id")
+                    .fetch_all(&self.pool)
+                    .await?
+            }
+        };
+
+        Ok(reactions)
+    }
+
+    async fn add_reaction(&self, command: &str, reaction: &str) -> Result<i64> {
+        let id = sqlx::query_scalar(
+            "INSERT INTO ai_reactions (command, reaction) VALUES ($1, $2) RETURNING id",
+        )
+        .bind(command)
+        .bind(reaction)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    async fn remove_reaction(&self, id: i64) -> Result<bool> {
+        let deleted = sqlx::query("DELETE FROM ai_reactions WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        Ok(deleted > 0)
+    }
+
+    async fn resolve_alias(&self, alias: &str) -> Result<Option<String>> {
+        let command = sqlx::query_scalar("SELECT command FROM ai_command_aliases WHERE alias = $1")
+            .bind(alias)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(command)
+    }
+
+    async fn list_aliases(&self) -> Result<Vec<AiCommandAlias>> {
+        let aliases = sqlx::query_as("SELECT * FROM ai_command_aliases ORDER BY command, alias")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(aliases)
+    }
+
+    async fn set_alias(&self, alias: &str, command: &str) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO ai_command_aliases (command, alias) VALUES ($1, $2)
+            ON CONFLICT (alias) DO UPDATE SET command = excluded.command",
+        )
+        .bind(command)
+        .bind(alias)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_alias(&self, alias: &str) -> Result<bool> {
+        let deleted = sqlx::query("DELETE FROM ai_command_aliases WHERE alias = $1")
+            .bind(alias)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        Ok(deleted > 0)
+    }
+}
+
+#[async_trait]
+impl ContentRepository for PostgresDatabase {
+    async fn list_help_messages(&self) -> Result<Vec<HelpMessage>> {
+        let help_messages = sqlx::query_as("SELECT * FROM help_messages")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(help_messages)
+    }
+
+    async fn list_leet(&self) -> Result<Vec<Leet>> {
+        let leet =
+            sqlx::query_as::<_, (i64, String, String)>("SELECT id, source, translated FROM leet")
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .filter_map(|(id, source, translated)| {
+                    Some(Leet {
+                        id,
+                        source: source.chars().next()?,
+                        translated,
+                    })
+                })
+                .collect();
+
+        Ok(leet)
+    }
+
+    async fn replace_content(&self, content: &BotContent) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        if let Some(statuses) = &content.statuses {
+            sqlx::query("DELETE FROM statuses")
+                .execute(&mut *tx)
+                .await?;
+
+            for status in statuses {
+                sqlx::query(
+                    "INSERT INTO statuses (id, type, status, weight, active_days, starts_at, ends_at)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                )
+                .bind(status.id)
+                .bind(status.r#type.as_str())
+                .bind(&status.status)
+                .bind(status.weight)
+                .bind(&status.active_days)
+                .bind(status.starts_at)
+                .bind(status.ends_at)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        if let Some(help_messages) = &content.help_messages {
+            sqlx::query("DELETE FROM help_messages")
+                .execute(&mut *tx)
+                .await?;
+
+            for help_message in help_messages {
+                sqlx::query("INSERT INTO help_messages (id, cmd, \"desc\") VALUES ($1, $2, $3)")
+                    .bind(help_message.id)
+                    .bind(&help_message.cmd)
+                    .bind(&help_message.desc)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+
+        if let Some(leet) = &content.leet {
+            sqlx::query("DELETE FROM leet").execute(&mut *tx).await?;
+
+            for row in leet {
+                sqlx::query("INSERT INTO leet (id, source, translated) VALUES ($1, $2, $3)")
+                    .bind(row.id)
+                    .bind(row.source.to_string())
+                    .bind(&row.translated)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+
+        if let Some(reactions) = &content.ai_reactions {
+            sqlx::query("DELETE FROM ai_reactions")
+                .execute(&mut *tx)
+                .await?;
+
+            for reaction in reactions {
+                sqlx::query("INSERT INTO ai_reactions (id, command, reaction) VALUES ($1, $2, $3)")
+                    .bind(reaction.id)
+                    .bind(&reaction.command)
+                    .bind(&reaction.reaction)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+
+        if let Some(aliases) = &content.ai_command_aliases {
+            sqlx::query("DELETE FROM ai_command_aliases")
+                .execute(&mut *tx)
+                .await?;
+
+            for alias in aliases {
+                sqlx::query(
+                    "INSERT INTO ai_command_aliases (id, command, alias) VALUES ($1, $2, $3)",
+                )
+                .bind(alias.id)
+                .bind(&alias.command)
+                .bind(&alias.alias)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        if let Some(prefixes) = &content.prefixes {
+            sqlx::query("DELETE FROM prefixes")
+                .execute(&mut *tx)
+                .await?;
+
+            for prefix in prefixes {
+                sqlx::query("INSERT INTO prefixes (id, server_id, prefix) VALUES ($1, $2, $3)")
+                    .bind(prefix.id)
+                    .bind(&prefix.server_id)
+                    .bind(&prefix.prefix)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+
+        // Inserting explicit Ids doesn't advance the identity sequences, so
+        // move them past the imported rows.
+        for table in CONTENT_TABLES {
+            sqlx::query(&format!(
+                "SELECT setval(pg_get_serial_sequence('{table}', 'id'), COALESCE(MAX(id), 0) + 1, false)
+                FROM {table}"
+            ))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RedditRepository for PostgresDatabase {
+    async fn replace_reddit_posts(
+        &self,
+        subreddit: &str,
+        posts: &[RedditPost],
+        fetched_at: i64,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM reddit_posts WHERE subreddit = $1")
+            .bind(subreddit)
+            .execute(&mut *tx)
+            .await?;
+
+        for post in posts {
+            sqlx::query(
+                "INSERT INTO reddit_posts (subreddit, title, url, over_18, permalink)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT DO NOTHING",
+            )
+            .bind(subreddit)
+            .bind(&post.title)
+            .bind(&post.url)
+            .bind(post.over_18)
+            .bind(&post.permalink)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query(
+            "INSERT INTO subreddits (name, requested_at, fetched_at) VALUES ($1, $2, $2)
+            ON CONFLICT (name) DO UPDATE SET fetched_at = excluded.fetched_at",
+        )
+        .bind(subreddit)
+        .bind(fetched_at)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn random_reddit_post(
+        &self,
+        subreddit: &str,
+        allow_nsfw: bool,
+    ) -> Result<Option<RedditPost>> {
+        let post = sqlx::query_as(
+            "SELECT * FROM reddit_posts WHERE subreddit = $1
+            ORDER BY (over_18 AND NOT $2), RANDOM() LIMIT 1",
+        )
+        .bind(subreddit)
+        .bind(allow_nsfw)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(post)
+    }
+
+    async fn touch_subreddit(&self, subreddit: &str, requested_at: i64) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO subreddits (name, requested_at) VALUES ($1, $2)
+            ON CONFLICT (name) DO UPDATE SET requested_at = excluded.requested_at",
+        )
+        .bind(subreddit)
+        .bind(requested_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn subreddit_fetched_at(&self, subreddit: &str) -> Result<Option<i64>> {
+        let fetched_at: Option<Option<i64>> =
+            sqlx::query_scalar("SELECT fetched_at FROM subreddits WHERE name = $1")
+                .bind(subreddit)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(fetched_at.flatten())
+    }
+
+    async fn cached_subreddits(&self, requested_after: i64) -> Result<Vec<String>> {
+        let subreddits = sqlx::query_scalar(
+            "SELECT name FROM subreddits
+            WHERE fetched_at IS NOT NULL AND requested_at > $1",
+        )
+        .bind(requested_after)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(subreddits)
+    }
+
+    async fn expire_subreddits(&self, requested_before: i64) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "DELETE FROM reddit_posts WHERE subreddit IN
+            (SELECT name FROM subreddits WHERE requested_at < $1)",
+        )
+        .bind(requested_before)
+        .execute(&mut *tx)
+        .await?;
+
+        let expired = sqlx::query("DELETE FROM subreddits WHERE requested_at < $1")
+            .bind(requested_before)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        tx.commit().await?;
+
+        Ok(expired)
+    }
+}
+
+#[async_trait]
+impl GuildSettingsRepository for PostgresDatabase {
+    async fn nsfw_enabled(&self, server_id: &str) -> Result<Option<bool>> {
+        let enabled =
+            sqlx::query_scalar("SELECT nsfw_enabled FROM guild_settings WHERE server_id = $1")
+                .bind(server_id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(enabled)
+    }
+
+    async fn set_nsfw_enabled(&self, server_id: &str, enabled: bool) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO guild_settings (server_id, nsfw_enabled) VALUES ($1, $2)
+            ON CONFLICT (server_id) DO UPDATE SET nsfw_enabled = excluded.nsfw_enabled",
+        )
+        .bind(server_id)
+        .bind(enabled)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ExchangeRepository for PostgresDatabase {
+    async fn cached_rate(&self, base: &str, target: &str, newer_than: i64) -> Result<Option<f64>> {
+        let rate = sqlx::query_scalar(
+            "SELECT rate FROM exchange_rates WHERE base = $1 AND target = $2 AND fetched_at > $3",
+        )
+        .bind(base)
+        .bind(target)
+        .bind(newer_than)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(rate)
+    }
+
+    async fn store_rates(
+        &self,
+        base: &str,
+        rates: &HashMap<String, f64>,
+        fetched_at: i64,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for (target, rate) in rates {
+            sqlx::query(
+                "INSERT INTO exchange_rates (base, target, rate, fetched_at)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (base, target)
+                DO UPDATE SET rate = excluded.rate, fetched_at = excluded.fetched_at",
+            )
+            .bind(base)
+            .bind(target)
+            .bind(rate)
+            .bind(fetched_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn list_currencies(&self) -> Result<Vec<(String, String)>> {
+        let currencies = sqlx::query_as("SELECT code, name FROM currencies ORDER BY code")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(currencies)
+    }
+
+    async fn store_currencies(&self, currencies: &[(String, String)]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for (code, name) in currencies {
+            sqlx::query(
+                "INSERT INTO currencies (code, name) VALUES ($1, $2)
+                ON CONFLICT (code) DO UPDATE SET name = excluded.name",
+            )
+            .bind(code)
+            .bind(name)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl BackupRepository for PostgresDatabase {
+    fn supports_backups(&self) -> bool {
+        false
+    }
+
+    async fn backup_to(&self, _path: &Path) -> Result<()> {
+        bail!("Backups are only supported with SQLite, use pg_dump to back up Postgres")
+    }
+}
+
+/// These tests run against the Postgres server at `POSTGRES_TEST_URL`, each in
+/// a temporary database that is dropped afterwards. They are ignored by
+/// default, run them with
+/// `cargo test --no-default-features --features postgres -- --ignored`.
+#[cfg(test)]
+mod tests {
+    use std::{
+        env, process,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use sqlx::{Connection, PgConnection};
+
+    use super::*;
+    use crate::db::repository_tests;
+
+    static DATABASES: AtomicUsize = AtomicUsize::new(0);
+
+    /// A migrated database that only exists for a single test.
+    struct TestDatabase {
+        db: PostgresDatabase,
+        server: PgConnectOptions,
+        name: String,
+    }
+
+    impl TestDatabase {
+        async fn create() -> Self {
+            let url = env::var("POSTGRES_TEST_URL")
+                .expect("POSTGRES_TEST_URL has to point to a Postgres server");
+
+            let server = PgConnectOptions::from_str(&url).expect("POSTGRES_TEST_URL is valid");
+            let name = format!(
+                "hifumi_test_{}_{}",
+                process::id(),
+                DATABASES.fetch_add(1, Ordering::SeqCst)
+            );
+
+            let mut conn = PgConnection::connect_with(&server).await.unwrap();
+            sqlx::query(&format!("CREATE DATABASE {name} TEMPLATE template0"))
+                .execute(&mut conn)
+                .await
+                .unwrap();
+            conn.close().await.unwrap();
+
+            let db = PostgresDatabase::connect_with(server.clone().database(&name))
+                .await
+                .unwrap();
+
+            Self { db, server, name }
+        }
+
+        async fn remove(self) {
+            self.db.pool.close().await;
+
+            let mut conn = PgConnection::connect_with(&self.server).await.unwrap();
+            sqlx::query(&format!("DROP DATABASE {} WITH (FORCE)", self.name))
+                .execute(&mut conn)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres server at POSTGRES_TEST_URL"]
+    async fn errors() {
+        let test = TestDatabase::create().await;
+        repository_tests::errors(&test.db).await;
+        test.remove().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres server at POSTGRES_TEST_URL"]
+    async fn prefixes() {
+        let test = TestDatabase::create().await;
+        repository_tests::prefixes(&test.db).await;
+        test.remove().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres server at POSTGRES_TEST_URL"]
+    async fn statuses() {
+        let test = TestDatabase::create().await;
+        repository_tests::statuses(&test.db).await;
+        test.remove().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres server at POSTGRES_TEST_URL"]
+    async fn reactions() {
+        let test = TestDatabase::create().await;
+        repository_tests::reactions(&test.db).await;
+        test.remove().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres server at POSTGRES_TEST_URL"]
+    async fn content() {
+        let test = TestDatabase::create().await;
+        repository_tests::content(&test.db).await;
+        test.remove().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres server at POSTGRES_TEST_URL"]
+    async fn reddit() {
+        let test = TestDatabase::create().await;
+        repository_tests::reddit(&test.db).await;
+        test.remove().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres server at POSTGRES_TEST_URL"]
+    async fn guild_settings() {
+        let test = TestDatabase::create().await;
+        repository_tests::guild_settings(&test.db).await;
+        test.remove().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres server at POSTGRES_TEST_URL"]
+    async fn exchange() {
+        let test = TestDatabase::create().await;
+        repository_tests::exchange(&test.db).await;
+        test.remove().await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres server at POSTGRES_TEST_URL"]
+    async fn backups_are_unsupported() {
+        let test = TestDatabase::create().await;
+
+        assert!(!test.db.supports_backups());
+        assert!(test.db.backup_to(Path::new("backup.db")).await.is_err());
+
+        test.remove().await;
+    }
+}
@@ -0,0 +1,229 @@
+//! The data access layer of the bot. Every aggregate has its own repository
+//! trait, so command logic doesn't depend on a specific database and can be
+//! tested against in-memory implementations.
+
+use std::{collections::HashMap, path::Path};
+
+use anyhow::Result;
+use serenity::async_trait;
+
+use super::models::{
+    AiCommandAlias, AiReactions, BotContent, ErrorLog, HelpMessage, Leet, Prefix, RedditPost,
+    Status, StatusType,
+};
+
+#[async_trait]
+pub trait ErrorRepository: Send + Sync {
+    /// Stores the error, ignoring its `id`.
+    ///
+    /// # Returns
+    /// The Id of the stored error.
+    async fn insert_error(&self, error: &ErrorLog) -> Result<i64>;
+
+    /// Lists the newest `limit` errors, newest first.
+    async fn list_errors(&self, limit: i64) -> Result<Vec<ErrorLog>>;
+
+    async fn get_error(&self, id: i64) -> Result<Option<ErrorLog>>;
+
+    /// Lists the newest `limit` errors whose message, command or stack contain
+    /// the query, newest first.
+    async fn search_errors(&self, query: &str, limit: i64) -> Result<Vec<ErrorLog>>;
+
+    /// Deletes all errors from before the unix timestamp.
+    ///
+    /// # Returns
+    /// The number of deleted errors.
+    async fn delete_errors_before(&self, timestamp: i64) -> Result<u64>;
+}
+
+#[async_trait]
+pub trait PrefixRepository: Send + Sync {
+    async fn list_prefixes(&self) -> Result<Vec<Prefix>>;
+
+    /// Adds the prefix to the guild, doing nothing if it already has it.
+    async fn add_prefix(&self, server_id: &str, prefix: &str) -> Result<()>;
+
+    /// Replaces all prefixes of the guild with the given one.
+    async fn replace_prefixes(&self, server_id: &str, prefix: &str) -> Result<()>;
+
+    /// # Returns
+    /// Whether the guild had the prefix.
+    async fn remove_prefix(&self, server_id: &str, prefix: &str) -> Result<bool>;
+}
+
+#[async_trait]
+pub trait StatusRepository: Send + Sync {
+    async fn list_statuses(&self) -> Result<Vec<Status>>;
+
+    /// Adds a status with the default weight and no schedule.
+    ///
+    /// # Returns
+    /// The Id of the new status.
+    async fn add_status(&self, status_type: &StatusType, text: &str) -> Result<i64>;
+
+    /// # Returns
+    /// Whether the status existed.
+    async fn remove_status(&self, id: i64) -> Result<bool>;
+
+    /// # Returns
+    /// Whether the status exists.
+    async fn set_status_weight(&self, id: i64, weight: i64) -> Result<bool>;
+
+    /// # Returns
+    /// Whether the status exists.
+    async fn set_status_schedule(
+        &self,
+        id: i64,
+        active_days: Option<&str>,
+        starts_at: Option<i64>,
+        ends_at: Option<i64>,
+    ) -> Result<bool>;
+}
+
+#[async_trait]
+pub trait ReactionRepository: Send + Sync {
+    /// Lists the reactions of the command, or all reactions if no command is
+    /// given.
+    async fn list_reactions(&self, command: Option<&str>) -> Result<Vec<AiReactions>>;
+
+    /// # Returns
+    /// The Id of the new reaction.
+    async fn add_reaction(&self, command: &str, reaction: &str) -> Result<i64>;
+
+    /// # Returns
+    /// Whether the reaction existed.
+    async fn remove_reaction(&self, id: i64) -> Result<bool>;
+
+    /// Returns the command the alias stands for, if it is one.
+    async fn resolve_alias(&self, alias: &str) -> Result<Option<String>>;
+
+    async fn list_aliases(&self) -> Result<Vec<AiCommandAlias>>;
+
+    /// Makes the alias stand for the command, replacing what it stood for
+    /// before.
+    async fn set_alias(&self, alias: &str, command: &str) -> Result<()>;
+
+    /// # Returns
+    /// Whether the alias existed.
+    async fn remove_alias(&self, alias: &str) -> Result<bool>;
+}
+
+#[async_trait]
+pub trait ContentRepository: Send + Sync {
+    async fn list_help_messages(&self) -> Result<Vec<HelpMessage>>;
+
+    /// Lists all leet speak translations. Rows with an empty source are
+    /// skipped.
+    async fn list_leet(&self) -> Result<Vec<Leet>>;
+
+    /// Replaces the rows of every section the content contains, all in one
+    /// transaction.
+    async fn replace_content(&self, content: &BotContent) -> Result<()>;
+}
+
+#[async_trait]
+pub trait RedditRepository: Send + Sync {
+    /// Replaces the cached posts of the subreddit with the given ones and
+    /// remembers when they were fetched, even if there were none.
+    async fn replace_reddit_posts(
+        &self,
+        subreddit: &str,
+        posts: &[RedditPost],
+        fetched_at: i64,
+    ) -> Result<()>;
+
+    /// Picks a random cached post of the subreddit. NSFW posts are only
+    /// picked if `allow_nsfw` is set or there are no other posts.
+    async fn random_reddit_post(
+        &self,
+        subreddit: &str,
+        allow_nsfw: bool,
+    ) -> Result<Option<RedditPost>>;
+
+    /// Remembers that the subreddit was requested at the unix timestamp.
+    async fn touch_subreddit(&self, subreddit: &str, requested_at: i64) -> Result<()>;
+
+    /// Returns when the posts of the subreddit were last fetched, or `None`
+    /// if they never were.
+    async fn subreddit_fetched_at(&self, subreddit: &str) -> Result<Option<i64>>;
+
+    /// Lists the subreddits whose posts were fetched before and that were
+    /// requested after the unix timestamp.
+    async fn cached_subreddits(&self, requested_after: i64) -> Result<Vec<String>>;
+
+    /// Forgets the subreddits that weren't requested since the unix
+    /// timestamp, together with their cached posts.
+    ///
+    /// # Returns
+    /// The number of forgotten subreddits.
+    async fn expire_subreddits(&self, requested_before: i64) -> Result<u64>;
+}
+
+#[async_trait]
+pub trait GuildSettingsRepository: Send + Sync {
+    /// Returns whether the guild allows NSFW content, or `None` if it never
+    /// changed the setting.
+    async fn nsfw_enabled(&self, server_id: &str) -> Result<Option<bool>>;
+
+    async fn set_nsfw_enabled(&self, server_id: &str, enabled: bool) -> Result<()>;
+}
+
+#[async_trait]
+pub trait ExchangeRepository: Send + Sync {
+    /// Returns the cached rate if it was fetched after the unix timestamp.
+    async fn cached_rate(&self, base: &str, target: &str, newer_than: i64) -> Result<Option<f64>>;
+
+    /// Replaces the cached rates of the base currency.
+    async fn store_rates(
+        &self,
+        base: &str,
+        rates: &HashMap<String, f64>,
+        fetched_at: i64,
+    ) -> Result<()>;
+
+    /// Lists the supported currencies as code and name pairs, sorted by code.
+    async fn list_currencies(&self) -> Result<Vec<(String, String)>>;
+
+    async fn store_currencies(&self, currencies: &[(String, String)]) -> Result<()>;
+}
+
+#[async_trait]
+pub trait BackupRepository: Send + Sync {
+    /// Whether the backend can write snapshots of the database.
+    fn supports_backups(&self) -> bool;
+
+    /// Writes a consistent snapshot of the database to the file while the
+    /// bot keeps running. The file mustn't exist yet.
+    async fn backup_to(&self, path: &Path) -> Result<()>;
+}
+
+/// All repositories together, as used by the bot.
+pub trait Database:
+    ErrorRepository
+    + PrefixRepository
+    + StatusRepository
+    + ReactionRepository
+    + ContentRepository
+    + RedditRepository
+    + GuildSettingsRepository
+    + ExchangeRepository
+    + BackupRepository
+    + Send
+    + Sync
+{
+}
+
+impl<T> Database for T where
+    T: ErrorRepository
+        + PrefixRepository
+        + StatusRepository
+        + ReactionRepository
+        + ContentRepository
+        + RedditRepository
+        + GuildSettingsRepository
+        + ExchangeRepository
+        + BackupRepository
+        + Send
+        + Sync
+{
+}
@@ -0,0 +1,307 @@
+//! Tests every database backend runs against its repositories, so they all
+//! behave the same. Each test expects an empty, migrated database.
+
+use std::collections::HashMap;
+
+use super::{
+    models::{BotContent, ErrorLog, HelpMessage, Leet, RedditPost, Status, StatusType},
+    repositories::Database,
+};
+
+fn error(command: &str, timestamp: i64) -> ErrorLog {
+    ErrorLog {
+        id: 0,
+        server: None,
+        channel: "1".to_string(),
+        user: "2".to_string(),
+        command: Some(command.to_string()),
+        stack: None,
+        timestamp: Some(timestamp),
+        log: None,
+        error: Some(format!("{command} failed")),
+    }
+}
+
+fn post(url: &str, over_18: bool) -> RedditPost {
+    RedditPost {
+        id: 0,
+        subreddit: String::new(),
+        title: url.to_string(),
+        url: url.to_string(),
+        over_18,
+        permalink: format!("https://reddit.com/{url}"),
+    }
+}
+
+pub async fn errors(db: &dyn Database) {
+    let first = db.insert_error(&error("avatar", 100)).await.unwrap();
+    let second = db.insert_error(&error("convert", 200)).await.unwrap();
+    let third = db.insert_error(&error("avatar", 300)).await.unwrap();
+
+    let ids = |errors: Vec<ErrorLog>| errors.iter().map(|e| e.id).collect::<Vec<_>>();
+
+    assert_eq!(ids(db.list_errors(2).await.unwrap()), [third, second]);
+    assert_eq!(
+        ids(db.search_errors("avatar", 10).await.unwrap()),
+        [third, first]
+    );
+    assert_eq!(
+        db.get_error(second)
+            .await
+            .unwrap()
+            .unwrap()
+            .command
+            .as_deref(),
+        Some("convert")
+    );
+    assert!(db.get_error(third + 1).await.unwrap().is_none());
+
+    assert_eq!(db.delete_errors_before(250).await.unwrap(), 2);
+    assert_eq!(ids(db.list_errors(10).await.unwrap()), [third]);
+
+    // Wildcards in the query are matched literally.
+    let percent = db.insert_error(&error("100%", 400)).await.unwrap();
+    let underscore = db.insert_error(&error("a_b", 500)).await.unwrap();
+    let backslash = db.insert_error(&error("c\\d", 600)).await.unwrap();
+    assert_eq!(ids(db.search_errors("%", 10).await.unwrap()), [percent]);
+    assert_eq!(ids(db.search_errors("_", 10).await.unwrap()), [underscore]);
+    assert_eq!(ids(db.search_errors("\\", 10).await.unwrap()), [backslash]);
+    assert!(db.search_errors("a%b", 10).await.unwrap().is_empty());
+}
+
+pub async fn prefixes(db: &dyn Database) {
+    let guild_prefixes = || async {
+        let mut prefixes = db
+            .list_prefixes()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|row| row.server_id == "1")
+            .map(|row| row.prefix)
+            .collect::<Vec<_>>();
+        prefixes.sort();
+        prefixes
+    };
+
+    db.add_prefix("1", "h!").await.unwrap();
+    db.add_prefix("1", "h!").await.unwrap();
+    db.add_prefix("1", "!").await.unwrap();
+    db.add_prefix("2", "?").await.unwrap();
+    assert_eq!(guild_prefixes().await, ["!", "h!"]);
+
+    assert!(db.remove_prefix("1", "!").await.unwrap());
+    assert!(!db.remove_prefix("1", "!").await.unwrap());
+    assert_eq!(guild_prefixes().await, ["h!"]);
+
+    db.replace_prefixes("1", "h.").await.unwrap();
+    assert_eq!(guild_prefixes().await, ["h."]);
+    assert_eq!(db.list_prefixes().await.unwrap().len(), 2);
+}
+
+pub async fn statuses(db: &dyn Database) {
+    let id = db
+        .add_status(&StatusType::Playing, "with {servers} servers")
+        .await
+        .unwrap();
+    let removed = db.add_status(&StatusType::Watching, "you").await.unwrap();
+
+    assert!(db.set_status_weight(id, 5).await.unwrap());
+    assert!(db
+        .set_status_schedule(id, Some("sat,sun"), Some(100), Some(200))
+        .await
+        .unwrap());
+    assert!(db.remove_status(removed).await.unwrap());
+
+    assert!(!db.remove_status(removed).await.unwrap());
+    assert!(!db.set_status_weight(removed, 5).await.unwrap());
+    assert!(!db
+        .set_status_schedule(removed, None, None, None)
+        .await
+        .unwrap());
+
+    let statuses = db.list_statuses().await.unwrap();
+    assert_eq!(statuses.len(), 1);
+    let status = &statuses[0];
+    assert_eq!(status.id, id);
+    assert_eq!(status.r#type.as_str(), "Playing");
+    assert_eq!(status.status, "with {servers} servers");
+    assert_eq!(status.weight, 5);
+    assert_eq!(status.active_days.as_deref(), Some("sat,sun"));
+    assert_eq!((status.starts_at, status.ends_at), (Some(100), Some(200)));
+}
+
+pub async fn reactions(db: &dyn Database) {
+    let hug = db.add_reaction("hug", "*hugs*").await.unwrap();
+    let pat = db.add_reaction("pat", "*pats*").await.unwrap();
+    db.add_reaction("hug", "*squeezes*").await.unwrap();
+
+    let reactions = |command| async move {
+        db.list_reactions(command)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|row| row.reaction)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(reactions(Some("hug")).await, ["*hugs*", "*squeezes*"]);
+    assert_eq!(reactions(None).await, ["*hugs*", "*squeezes*", "*pats*"]);
+
+    assert!(db.remove_reaction(hug).await.unwrap());
+    assert!(!db.remove_reaction(hug).await.unwrap());
+    assert_eq!(reactions(Some("hug")).await, ["*squeezes*"]);
+    assert!(db.remove_reaction(pat).await.unwrap());
+
+    db.set_alias("cuddle", "pat").await.unwrap();
+    db.set_alias("cuddle", "hug").await.unwrap();
+    db.set_alias("squeeze", "hug").await.unwrap();
+    assert_eq!(
+        db.resolve_alias("cuddle").await.unwrap().as_deref(),
+        Some("hug")
+    );
+    assert!(db.resolve_alias("hug").await.unwrap().is_none());
+
+    let aliases = db
+        .list_aliases()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|row| (row.command, row.alias))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        aliases,
+        [
+            ("hug".to_string(), "cuddle".to_string()),
+            ("hug".to_string(), "squeeze".to_string())
+        ]
+    );
+
+    assert!(db.remove_alias("cuddle").await.unwrap());
+    assert!(!db.remove_alias("cuddle").await.unwrap());
+    assert!(db.resolve_alias("cuddle").await.unwrap().is_none());
+}
+
+pub async fn content(db: &dyn Database) {
+    db.add_reaction("hug", "*hugs*").await.unwrap();
+
+    let content = BotContent {
+        statuses: Some(vec![Status {
+            id: 10,
+            r#type: StatusType::Custom,
+            status: "Imported".to_string(),
+            weight: 2,
+            active_days: None,
+            starts_at: None,
+            ends_at: None,
+        }]),
+        help_messages: Some(vec![HelpMessage {
+            id: 3,
+            cmd: "avatar".to_string(),
+            desc: "Shows an avatar".to_string(),
+        }]),
+        leet: Some(vec![Leet {
+            id: 4,
+            source: 'a',
+            translated: "4".to_string(),
+        }]),
+        ai_reactions: Some(Vec::new()),
+        ai_command_aliases: None,
+        prefixes: None,
+    };
+    db.replace_content(&content).await.unwrap();
+
+    let statuses = db.list_statuses().await.unwrap();
+    assert_eq!(statuses.len(), 1);
+    assert_eq!((statuses[0].id, statuses[0].weight), (10, 2));
+
+    let help_messages = db.list_help_messages().await.unwrap();
+    assert_eq!(help_messages.len(), 1);
+    assert_eq!(help_messages[0].desc, "Shows an avatar");
+
+    let leet = db.list_leet().await.unwrap();
+    assert_eq!(leet.len(), 1);
+    assert_eq!((leet[0].source, leet[0].translated.as_str()), ('a', "4"));
+
+    assert!(db.list_reactions(None).await.unwrap().is_empty());
+
+    // New rows continue after the imported Ids.
+    let id = db.add_status(&StatusType::Playing, "new").await.unwrap();
+    assert!(id > 10);
+}
+
+pub async fn reddit(db: &dyn Database) {
+    db.touch_subreddit("aww", 100).await.unwrap();
+    assert!(db.subreddit_fetched_at("aww").await.unwrap().is_none());
+    assert!(db.cached_subreddits(0).await.unwrap().is_empty());
+
+    let posts = [
+        post("a.png", true),
+        post("b.png", false),
+        post("b.png", false),
+    ];
+    db.replace_reddit_posts("aww", &posts, 150).await.unwrap();
+    db.replace_reddit_posts("empty", &[], 150).await.unwrap();
+    assert_eq!(db.subreddit_fetched_at("aww").await.unwrap(), Some(150));
+    assert_eq!(db.subreddit_fetched_at("empty").await.unwrap(), Some(150));
+
+    for _ in 0..10 {
+        let post = db.random_reddit_post("aww", false).await.unwrap().unwrap();
+        assert_eq!(post.url, "b.png");
+    }
+    assert!(db
+        .random_reddit_post("empty", true)
+        .await
+        .unwrap()
+        .is_none());
+
+    db.replace_reddit_posts("aww", &[post("a.png", true)], 200)
+        .await
+        .unwrap();
+    let post = db.random_reddit_post("aww", false).await.unwrap().unwrap();
+    assert_eq!((post.url.as_str(), post.over_18), ("a.png", true));
+
+    db.touch_subreddit("aww", 300).await.unwrap();
+    let mut cached = db.cached_subreddits(120).await.unwrap();
+    cached.sort();
+    assert_eq!(cached, ["aww", "empty"]);
+    assert_eq!(db.cached_subreddits(200).await.unwrap(), ["aww"]);
+
+    assert_eq!(db.expire_subreddits(200).await.unwrap(), 1);
+    assert!(db.subreddit_fetched_at("empty").await.unwrap().is_none());
+    assert!(db.random_reddit_post("aww", true).await.unwrap().is_some());
+
+    assert_eq!(db.expire_subreddits(400).await.unwrap(), 1);
+    assert!(db.random_reddit_post("aww", true).await.unwrap().is_none());
+}
+
+pub async fn guild_settings(db: &dyn Database) {
+    assert_eq!(db.nsfw_enabled("1").await.unwrap(), None);
+    db.set_nsfw_enabled("1", false).await.unwrap();
+    assert_eq!(db.nsfw_enabled("1").await.unwrap(), Some(false));
+    db.set_nsfw_enabled("1", true).await.unwrap();
+    assert_eq!(db.nsfw_enabled("1").await.unwrap(), Some(true));
+}
+
+pub async fn exchange(db: &dyn Database) {
+    let rates = HashMap::from([("EUR".to_string(), 0.9), ("JPY".to_string(), 150.0)]);
+    db.store_rates("USD", &rates, 100).await.unwrap();
+    assert_eq!(db.cached_rate("USD", "EUR", 50).await.unwrap(), Some(0.9));
+    assert_eq!(db.cached_rate("USD", "EUR", 100).await.unwrap(), None);
+    assert_eq!(db.cached_rate("EUR", "USD", 50).await.unwrap(), None);
+
+    let rates = HashMap::from([("EUR".to_string(), 0.8)]);
+    db.store_rates("USD", &rates, 200).await.unwrap();
+    assert_eq!(db.cached_rate("USD", "EUR", 150).await.unwrap(), Some(0.8));
+
+    assert!(db.list_currencies().await.unwrap().is_empty());
+    let currencies = [
+        ("USD".to_string(), "US Dollar".to_string()),
+        ("EUR".to_string(), "Euro".to_string()),
+    ];
+    db.store_currencies(&currencies).await.unwrap();
+    db.store_currencies(&currencies[..1]).await.unwrap();
+    assert_eq!(
+        db.list_currencies().await.unwrap(),
+        [currencies[1].clone(), currencies[0].clone()]
+    );
+}
@@ -0,0 +1,811 @@
+use std::{collections::HashMap, path::Path, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use serenity::async_trait;
+use sqlx::{migrate::Migrator, sqlite::SqliteConnectOptions, SqlitePool};
+
+use super::{
+    escape_like,
+    models::{
+        AiCommandAlias, AiReactions, BotContent, ErrorLog, HelpMessage, Leet, Prefix, RedditPost,
+        Status, StatusType,
+    },
+    repositories::{
+        BackupRepository, ContentRepository, ErrorRepository, ExchangeRepository,
+        GuildSettingsRepository, PrefixRepository, ReactionRepository, RedditRepository,
+        StatusRepository,
+    },
+};
+
+/// The migrations of the SQLite schema, embedded into the binary.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+
+/// The SQLite implementation of all repositories.
+pub struct SqliteDatabase {
+    pool: SqlitePool,
+}
+
+impl SqliteDatabase {
+    /// Connects to the database at the URL, creating it if it doesn't exist
+    /// yet, and runs all pending migrations.
+    ///
+    /// # Errors
+    /// * If connecting or migrating fails.
+    pub async fn connect(url: &str) -> Result<Self> {
+        let options = SqliteConnectOptions::from_str(url)?.create_if_missing(true);
+        let pool = SqlitePool::connect_with(options).await?;
+
+        MIGRATOR.run(&pool).await?;
+
+        Ok(Self { pool })
+    }
+}
+
+#[async_trait]
+impl ErrorRepository for SqliteDatabase {
+    async fn insert_error(&self, error: &ErrorLog) -> Result<i64> {
+        let id = sqlx::query!(
+            "INSERT INTO error_log (server, channel, user, command, stack, timestamp, log, error)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            error.server,
+            error.channel,
+            error.user,
+            error.command,
+            error.stack,
+            error.timestamp,
+            error.log,
+            error.error,
+        )
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+
+        Ok(id)
+    }
+
+    async fn list_errors(&self, limit: i64) -> Result<Vec<ErrorLog>> {
+        let errors = sqlx::query_as!(
+            ErrorLog,
+            "SELECT * FROM error_log ORDER BY id DESC LIMIT ?",
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(errors)
+    }
+
+    async fn get_error(&self, id: i64) -> Result<Option<ErrorLog>> {
+        let error = sqlx::query_as!(ErrorLog, "SELECT * FROM error_log WHERE id = ?", id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(error)
+    }
+
+    async fn search_errors(&self, query: &str, limit: i64) -> Result<Vec<ErrorLog>> {
+        let query = escape_like(query);
+        let errors = sqlx::query_as!(
+            ErrorLog,
+            r"SELECT * FROM error_log
+            WHERE error LIKE '%' || ?1 || '%' ESCAPE '\'
+            OR command LIKE '%' || ?1 || '%' ESCAPE '\'
+            OR stack LIKE '%' || ?1 || '%' ESCAPE '\'
+            ORDER BY id DESC
+            LIMIT ?2",
+            query,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(errors)
+    }
+
+    async fn delete_errors_before(&self, timestamp: i64) -> Result<u64> {
+        let deleted = sqlx::query!("DELETE FROM error_log WHERE timestamp < ?", timestamp)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        Ok(deleted)
+    }
+}
+
+#[async_trait]
+impl PrefixRepository for SqliteDatabase {
+    async fn list_prefixes(&self) -> Result<Vec<Prefix>> {
+        let prefixes = sqlx::query_as!(Prefix, "SELECT * FROM prefixes")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(prefixes)
+    }
+
+    async fn add_prefix(&self, server_id: &str, prefix: &str) -> Result<()> {
+        sqlx::query!(
+            "INSERT OR IGNORE INTO prefixes (server_id, prefix) VALUES (?, ?)",
+            server_id,
+            prefix,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn replace_prefixes(&self, server_id: &str, prefix: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!("DELETE FROM prefixes WHERE server_id = ?", server_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query!(
+            "INSERT INTO prefixes (server_id, prefix) VALUES (?, ?)",
+            server_id,
+            prefix,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn remove_prefix(&self, server_id: &str, prefix: &str) -> Result<bool> {
+        let deleted = sqlx::query!(
+            "DELETE FROM prefixes WHERE server_id = ? AND prefix = ?",
+            server_id,
+            prefix,
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(deleted > 0)
+    }
+}
+
+#[async_trait]
+impl StatusRepository for SqliteDatabase {
+    async fn list_statuses(&self) -> Result<Vec<Status>> {
+        let statuses = sqlx::query_as!(Status, "SELECT * FROM statuses")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(statuses)
+    }
+
+    async fn add_status(&self, status_type: &StatusType, text: &str) -> Result<i64> {
+        let type_name = status_type.as_str();
+
+        let id = sqlx::query!(
+            "INSERT INTO statuses (type, status) VALUES (?, ?)",
+            type_name,
+            text,
+        )
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+
+        Ok(id)
+    }
+
+    async fn remove_status(&self, id: i64) -> Result<bool> {
+        let deleted = sqlx::query!("DELETE FROM statuses WHERE id = ?", id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        Ok(deleted > 0)
+    }
+
+    async fn set_status_weight(&self, id: i64, weight: i64) -> Result<bool> {
+        let updated = sqlx::query!("UPDATE statuses SET weight = ? WHERE id = ?", weight, id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        Ok(updated > 0)
+    }
+
+    async fn set_status_schedule(
+        &self,
+        id: i64,
+        active_days: Option<&str>,
+        starts_at: Option<i64>,
+        ends_at: Option<i64>,
+    ) -> Result<bool> {
+        let updated = sqlx::query!(
+            "UPDATE statuses SET active_days = ?, starts_at = ?, ends_at = ? WHERE id = ?",
+            active_days,
+            starts_at,
+            ends_at,
+            id
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(updated > 0)
+    }
+}
+
+#[async_trait]
+impl ReactionRepository for SqliteDatabase {
+    async fn list_reactions(&self, command: Option<&str>) -> Result<Vec<AiReactions>> {
+        let reactions = match command {
+            Some(command) => {
+                sqlx::query_as!(
+                    AiReactions,
+                    "SELECT * FROM ai_reactions WHERE command = ? ORDER BY id",
+                    command
+                )
+                .fetch_all(&self.pool)
+                .await?
+            }
+            None => {
+                sqlx::query_as!(
+                    AiReactions,
+                    "SELECT * FROM ai_reactions ORDER BY command, id"
+                )
+                .fetch_all(&self.pool)
+                .await?
+            }
+        };
+
+        Ok(reactions)
+    }
+
+    async fn add_reaction(&self, command: &str, reaction: &str) -> Result<i64> {
+        let id = sqlx::query!(
+            "INSERT INTO ai_reactions (command, reaction) VALUES (?, ?)",
+            command,
+            reaction
+        )
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+
+        Ok(id)
+    }
+
+    async fn remove_reaction(&self, id: i64) -> Result<bool> {
+        let deleted = sqlx::query!("DELETE FROM ai_reactions WHERE id = ?", id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        Ok(deleted > 0)
+    }
+
+    async fn resolve_alias(&self, alias: &str) -> Result<Option<String>> {
+        let command = sqlx::query!(
+            "SELECT command FROM ai_command_aliases WHERE alias = ?",
+            alias
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .map(|row| row.command);
+
+        Ok(command)
+    }
+
+    async fn list_aliases(&self) -> Result<Vec<AiCommandAlias>> {
+        let aliases = sqlx::query_as!(
+            AiCommandAlias,
+            "SELECT * FROM ai_command_aliases ORDER BY command, alias"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(aliases)
+    }
+
+    async fn set_alias(&self, alias: &str, command: &str) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO ai_command_aliases (command, alias) VALUES (?, ?)
+            ON CONFLICT (alias) DO UPDATE SET command = excluded.command",
+            command,
+            alias
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_alias(&self, alias: &str) -> Result<bool> {
+        let deleted = sqlx::query!("DELETE FROM ai_command_aliases WHERE alias = ?", alias)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        Ok(deleted > 0)
+    }
+}
+
+#[async_trait]
+impl ContentRepository for SqliteDatabase {
+    async fn list_help_messages(&self) -> Result<Vec<HelpMessage>> {
+        let help_messages = sqlx::query_as!(HelpMessage, "SELECT * FROM help_messages")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(help_messages)
+    }
+
+    async fn list_leet(&self) -> Result<Vec<Leet>> {
+        let leet = sqlx::query!("SELECT id, source, translated FROM leet")
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .filter_map(|row| {
+                Some(Leet {
+                    id: row.id,
+                    source: row.source.chars().next()?,
+                    translated: row.translated,
+                })
+            })
+            .collect();
+
+        Ok(leet)
+    }
+
+    async fn replace_content(&self, content: &BotContent) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        if let Some(statuses) = &content.statuses {
+            sqlx::query!("DELETE FROM statuses")
+                .execute(&mut *tx)
+                .await?;
+
+            for status in statuses {
+                let type_name = status.r#type.as_str();
+
+                sqlx::query!(
+                    "INSERT INTO statuses (id, type, status, weight, active_days, starts_at, ends_at)
+                    VALUES (?, ?, ?, ?, ?, ?, ?)",
+                    status.id,
+                    type_name,
+                    status.status,
+                    status.weight,
+                    status.active_days,
+                    status.starts_at,
+                    status.ends_at,
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        if let Some(help_messages) = &content.help_messages {
+            sqlx::query!("DELETE FROM help_messages")
+                .execute(&mut *tx)
+                .await?;
+
+            for help_message in help_messages {
+                sqlx::query!(
+                    "INSERT INTO help_messages (id, cmd, \"desc\") VALUES (?, ?, ?)",
+                    help_message.id,
+                    help_message.cmd,
+                    help_message.desc,
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        if let Some(leet) = &content.leet {
+            sqlx::query!("DELETE FROM leet").execute(&mut *tx).await?;
+
+            for row in leet {
+                let source = row.source.to_string();
+
+                sqlx::query!(
+                    "INSERT INTO leet (id, source, translated) VALUES (?, ?, ?)",
+                    row.id,
+                    source,
+                    row.translated,
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        if let Some(reactions) = &content.ai_reactions {
+            sqlx::query!("DELETE FROM ai_reactions")
+                .execute(&mut *tx)
+                .await?;
+
+            for reaction in reactions {
+                sqlx::query!(
+                    "INSERT INTO ai_reactions (id, command, reaction) VALUES (?, ?, ?)",
+                    reaction.id,
+                    reaction.command,
+                    reaction.reaction,
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        if let Some(aliases) = &content.ai_command_aliases {
+            sqlx::query!("DELETE FROM ai_command_aliases")
+                .execute(&mut *tx)
+                .await?;
+
+            for alias in aliases {
+                sqlx::query!(
+                    "INSERT INTO ai_command_aliases (id, command, alias) VALUES (?, ?, ?)",
+                    alias.id,
+                    alias.command,
+                    alias.alias,
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        if let Some(prefixes) = &content.prefixes {
+            sqlx::query!("DELETE FROM prefixes")
+                .execute(&mut *tx)
+                .await?;
+
+            for prefix in prefixes {
+                sqlx::query!(
+                    "INSERT INTO prefixes (id, server_id, prefix) VALUES (?, ?, ?)",
+                    prefix.id,
+                    prefix.server_id,
+                    prefix.prefix,
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RedditRepository for SqliteDatabase {
+    async fn replace_reddit_posts(
+        &self,
+        subreddit: &str,
+        posts: &[RedditPost],
+        fetched_at: i64,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!("DELETE FROM reddit_posts WHERE subreddit = ?", subreddit)
+            .execute(&mut *tx)
+            .await?;
+
+        for post in posts {
+            sqlx::query!(
+                "INSERT OR IGNORE INTO reddit_posts (subreddit, title, url, over_18, permalink)
+                VALUES (?, ?, ?, ?, ?)",
+                subreddit,
+                post.title,
+                post.url,
+                post.over_18,
+                post.permalink,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query!(
+            "INSERT INTO subreddits (name, requested_at, fetched_at) VALUES (?1, ?2, ?2)
+            ON CONFLICT(name) DO UPDATE SET fetched_at = excluded.fetched_at",
+            subreddit,
+            fetched_at
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn random_reddit_post(
+        &self,
+        subreddit: &str,
+        allow_nsfw: bool,
+    ) -> Result<Option<RedditPost>> {
+        let post = sqlx::query_as!(
+            RedditPost,
+            "SELECT * FROM reddit_posts WHERE subreddit = ?
+            ORDER BY (over_18 AND NOT ?), RANDOM() LIMIT 1",
+            subreddit,
+            allow_nsfw
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(post)
+    }
+
+    async fn touch_subreddit(&self, subreddit: &str, requested_at: i64) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO subreddits (name, requested_at) VALUES (?, ?)
+            ON CONFLICT(name) DO UPDATE SET requested_at = excluded.requested_at",
+            subreddit,
+            requested_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn subreddit_fetched_at(&self, subreddit: &str) -> Result<Option<i64>> {
+        let fetched_at = sqlx::query!(
+            "SELECT fetched_at FROM subreddits WHERE name = ?",
+            subreddit
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .and_then(|row| row.fetched_at);
+
+        Ok(fetched_at)
+    }
+
+    async fn cached_subreddits(&self, requested_after: i64) -> Result<Vec<String>> {
+        let subreddits = sqlx::query!(
+            "SELECT name FROM subreddits
+            WHERE fetched_at IS NOT NULL AND requested_at > ?",
+            requested_after
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| row.name)
+        .collect();
+
+        Ok(subreddits)
+    }
+
+    async fn expire_subreddits(&self, requested_before: i64) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            "DELETE FROM reddit_posts WHERE subreddit IN
+            (SELECT name FROM subreddits WHERE requested_at < ?)",
+            requested_before
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let expired = sqlx::query!(
+            "DELETE FROM subreddits WHERE requested_at < ?",
+            requested_before
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        tx.commit().await?;
+
+        Ok(expired)
+    }
+}
+
+#[async_trait]
+impl GuildSettingsRepository for SqliteDatabase {
+    async fn nsfw_enabled(&self, server_id: &str) -> Result<Option<bool>> {
+        let enabled = sqlx::query!(
+            "SELECT nsfw_enabled FROM guild_settings WHERE server_id = ?",
+            server_id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .map(|settings| settings.nsfw_enabled);
+
+        Ok(enabled)
+    }
+
+    async fn set_nsfw_enabled(&self, server_id: &str, enabled: bool) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO guild_settings (server_id, nsfw_enabled) VALUES (?, ?)
+            ON CONFLICT(server_id) DO UPDATE SET nsfw_enabled = excluded.nsfw_enabled",
+            server_id,
+            enabled,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ExchangeRepository for SqliteDatabase {
+    async fn cached_rate(&self, base: &str, target: &str, newer_than: i64) -> Result<Option<f64>> {
+        let rate = sqlx::query!(
+            "SELECT rate FROM exchange_rates WHERE base = ? AND target = ? AND fetched_at > ?",
+            base,
+            target,
+            newer_than,
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .map(|row| row.rate);
+
+        Ok(rate)
+    }
+
+    async fn store_rates(
+        &self,
+        base: &str,
+        rates: &HashMap<String, f64>,
+        fetched_at: i64,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for (target, rate) in rates {
+            sqlx::query!(
+                "INSERT OR REPLACE INTO exchange_rates (base, target, rate, fetched_at)
+                VALUES (?, ?, ?, ?)",
+                base,
+                target,
+                rate,
+                fetched_at,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn list_currencies(&self) -> Result<Vec<(String, String)>> {
+        let currencies = sqlx::query!("SELECT code, name FROM currencies ORDER BY code")
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|row| (row.code, row.name))
+            .collect();
+
+        Ok(currencies)
+    }
+
+    async fn store_currencies(&self, currencies: &[(String, String)]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for (code, name) in currencies {
+            sqlx::query!(
+                "INSERT OR REPLACE INTO currencies (code, name) VALUES (?, ?)",
+                code,
+                name,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl BackupRepository for SqliteDatabase {
+    fn supports_backups(&self) -> bool {
+        true
+    }
+
+    async fn backup_to(&self, path: &Path) -> Result<()> {
+        let path = path
+            .to_str()
+            .ok_or_else(|| anyhow!("The backup path {} isn't valid UTF-8", path.display()))?;
+
+        sqlx::query!("VACUUM INTO ?", path)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::db::repository_tests;
+
+    /// Creates a migrated in-memory database. Every connection to
+    /// `sqlite::memory:` opens a new database, so the pool keeps a single
+    /// connection open for as long as it exists.
+    async fn memory_database() -> SqliteDatabase {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        MIGRATOR.run(&pool).await.unwrap();
+
+        SqliteDatabase { pool }
+    }
+
+    #[tokio::test]
+    async fn errors() {
+        repository_tests::errors(&memory_database().await).await;
+    }
+
+    #[tokio::test]
+    async fn prefixes() {
+        repository_tests::prefixes(&memory_database().await).await;
+    }
+
+    #[tokio::test]
+    async fn statuses() {
+        repository_tests::statuses(&memory_database().await).await;
+    }
+
+    #[tokio::test]
+    async fn reactions() {
+        repository_tests::reactions(&memory_database().await).await;
+    }
+
+    #[tokio::test]
+    async fn content() {
+        repository_tests::content(&memory_database().await).await;
+    }
+
+    #[tokio::test]
+    async fn reddit() {
+        repository_tests::reddit(&memory_database().await).await;
+    }
+
+    #[tokio::test]
+    async fn guild_settings() {
+        repository_tests::guild_settings(&memory_database().await).await;
+    }
+
+    #[tokio::test]
+    async fn exchange() {
+        repository_tests::exchange(&memory_database().await).await;
+    }
+
+    #[tokio::test]
+    async fn backs_up_into_a_copy() {
+        // Backups of in-memory databases stay in memory as well, so this one
+        // needs a file.
+        let dir = env::temp_dir().join(format!("hifumi-sqlite-backup-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let url = |name: &str| format!("sqlite://{}", dir.join(name).display());
+
+        let db = SqliteDatabase::connect(&url("bot.db")).await.unwrap();
+        db.add_prefix("1", "h!").await.unwrap();
+
+        assert!(db.supports_backups());
+        db.backup_to(&dir.join("backup.db")).await.unwrap();
+        db.pool.close().await;
+
+        let copy = SqliteDatabase::connect(&url("backup.db")).await.unwrap();
+        let prefixes = copy.list_prefixes().await.unwrap();
+        copy.pool.close().await;
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(prefixes.len(), 1);
+        assert_eq!(
+            (prefixes[0].server_id.as_str(), prefixes[0].prefix.as_str()),
+            ("1", "h!")
+        );
+    }
+}
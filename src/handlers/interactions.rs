@@ -0,0 +1,54 @@
+use anyhow::Result;
+use serenity::{all::CommandInteraction, prelude::*};
+
+use crate::{
+    commands::find_command,
+    helpers::types::{CommandData, CommandSource, Handler},
+};
+
+pub async fn handle_command_interaction(
+    handler: &Handler,
+    ctx: &Context,
+    interaction: &CommandInteraction,
+) -> Result<()> {
+    let Some(cmd) = find_command(&interaction.data.name) else {
+        return Ok(());
+    };
+
+    // Replies are sent as followups, so the command can take longer than the
+    // three seconds Discord gives us to respond.
+    interaction.defer(&ctx.http).await?;
+
+    let source = CommandSource::Interaction(interaction);
+
+    if !cmd.check_access(ctx, handler, source).await? {
+        return Ok(());
+    }
+
+    if !cmd.check_cooldown(ctx, handler, source).await? {
+        return Ok(());
+    }
+
+    let allow_nsfw = cmd.allows_nsfw(ctx, handler, source).await?;
+
+    debug!("{} used slash command: {}", interaction.user.id, cmd.name);
+
+    let args = match cmd.parse_slash_args(&interaction.data.options()) {
+        Ok(args) => args,
+        Err(e) => {
+            source.reply(ctx, e.to_string()).await?;
+            return Ok(());
+        }
+    };
+
+    (cmd.run)(CommandData {
+        ctx,
+        source,
+        args,
+        react_cmd: None,
+        handler,
+        prefix: "/".to_string(),
+        allow_nsfw,
+    })
+    .await
+}
@@ -4,28 +4,50 @@ use anyhow::Result;
 use serenity::{model::prelude::*, prelude::*};
 
 use crate::{
-    commands::misc::user_avatar,
+    commands::{
+        find_command,
+        fun::{react, REACTION_ARGS},
+    },
     helpers::{
-        types::{Handler, MessageCommandData},
+        args::parse_args,
+        types::{CommandData, CommandSource, Handler},
         utils::{is_indev, register_prefix},
     },
 };
 
-pub async fn handle_message(handler: &Handler<'_>, ctx: &Context, msg: &Message) -> Result<()> {
+pub async fn handle_message(handler: &Handler, ctx: &Context, msg: &Message) -> Result<()> {
     if msg.author.bot {
         return Ok(());
     }
-    let content = msg
+    let react_cmd = msg
         .content
         .split_whitespace()
-        .map(str::to_lowercase)
-        .collect::<Vec<String>>();
+        .next()
+        .and_then(|cmd| cmd.strip_prefix('$').map(str::to_lowercase))
+        .filter(|cmd| !cmd.is_empty());
+
+    if let Some(react_cmd) = react_cmd {
+        let raw_args = msg
+            .content
+            .trim_start()
+            .split_once(char::is_whitespace)
+            .map_or("", |(_, rest)| rest);
 
-    let react_cmd = content
-        .first()
-        .and_then(|cmd| cmd.strip_prefix('$').map(str::to_lowercase));
+        let Ok(args) = parse_args(raw_args, REACTION_ARGS) else {
+            return Ok(());
+        };
 
-    let sub_cmd = content.get(1).map(|cmd| cmd.to_lowercase());
+        return react(CommandData {
+            ctx,
+            source: CommandSource::Message(msg),
+            args,
+            react_cmd: Some(react_cmd),
+            handler,
+            prefix: "$".to_string(),
+            allow_nsfw: false,
+        })
+        .await;
+    }
 
     if let Some(guild_id) = msg.guild_id {
         if !handler
@@ -45,8 +67,8 @@ pub async fn handle_message(handler: &Handler<'_>, ctx: &Context, msg: &Message)
         }
     }
 
-    let prefix = if is_indev() {
-        "h?".to_string()
+    let guild_prefixes = if is_indev() {
+        vec!["h?".to_string()]
     } else {
         match msg.guild_id {
             Some(id) => handler
@@ -54,44 +76,87 @@ pub async fn handle_message(handler: &Handler<'_>, ctx: &Context, msg: &Message)
                 .read()
                 .await
                 .get(&id.to_string())
-                .map_or("h!".to_string(), ToString::to_string),
-            None => "h!".to_string(),
+                .filter(|prefixes| !prefixes.is_empty())
+                .cloned()
+                .unwrap_or_else(|| vec![handler.config().default_prefix.clone()]),
+            None => vec![handler.config().default_prefix.clone()],
         }
-    }
-    .to_lowercase();
+    };
 
-    if msg.content.to_lowercase().starts_with(&prefix) {
-        let command = content[0].replace(&prefix, "");
+    let bot_id = ctx.cache.current_user().id;
+    let mentions = [format!("<@{bot_id}>"), format!("<@!{bot_id}>")];
 
-        debug!("{} used command: {}", msg.author.id, command);
+    let candidates = guild_prefixes.iter().chain(mentions.iter());
+    let Some(matched_prefix) = longest_matching_prefix(&msg.content, candidates) else {
+        return Ok(());
+    };
 
-        handle_command(MessageCommandData {
-            ctx,
-            msg,
-            content,
-            command,
-            react_cmd,
-            sub_cmd,
-            handler,
-            prefix,
-        })
-        .await?;
+    // Mentions make for unreadable usage messages, so show the guild's
+    // first prefix instead when the bot was mentioned.
+    let prefix = if mentions.iter().any(|mention| mention == matched_prefix) {
+        guild_prefixes[0].to_lowercase()
+    } else {
+        matched_prefix.to_lowercase()
+    };
+
+    let body = msg.content[matched_prefix.len()..].trim_start();
+    let (command, raw_args) = body.split_once(char::is_whitespace).unwrap_or((body, ""));
+    let command = command.to_lowercase();
+
+    let Some(cmd) = find_command(&command) else {
+        return Ok(());
+    };
+
+    let source = CommandSource::Message(msg);
+
+    if !cmd.check_access(ctx, handler, source).await? {
+        return Ok(());
     }
 
-    Ok(())
-}
+    // Only checked once access was granted, so members who can't use the
+    // command can't use up its cooldown for everyone else.
+    if !cmd.check_cooldown(ctx, handler, source).await? {
+        return Ok(());
+    }
 
-async fn handle_command(data: MessageCommandData<'_>) -> Result<()> {
-    match data.command.as_str() {
-        "pfp" | "avatar" => user_avatar(data).await?,
-        "test" => {
-            data.msg
-                .channel_id
-                .say(&data.ctx.http, "Test command")
-                .await?;
+    debug!("{} used command: {}", msg.author.id, cmd.name);
+
+    let allow_nsfw = cmd.allows_nsfw(ctx, handler, source).await?;
+
+    let args = match cmd.parse_message_args(raw_args, &prefix) {
+        Ok(args) => args,
+        Err(e) => {
+            msg.channel_id.say(&ctx.http, e.to_string()).await?;
+            return Ok(());
         }
-        _ => {}
-    }
+    };
+
+    (cmd.run)(CommandData {
+        ctx,
+        source,
+        args,
+        react_cmd: None,
+        handler,
+        prefix,
+        allow_nsfw,
+    })
+    .await?;
 
     Ok(())
 }
+
+/// Finds the longest of the given prefixes the content starts with, ignoring
+/// case, so that e.g. `h!!` wins over `h!` if a guild has both.
+fn longest_matching_prefix<'a>(
+    content: &str,
+    prefixes: impl Iterator<Item = &'a String>,
+) -> Option<&'a str> {
+    prefixes
+        .filter(|prefix| {
+            content
+                .get(..prefix.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+        })
+        .max_by_key(|prefix| prefix.len())
+        .map(String::as_str)
+}
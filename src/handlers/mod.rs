@@ -1 +1,2 @@
+pub mod interactions;
 pub mod messages;
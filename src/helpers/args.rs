@@ -0,0 +1,528 @@
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::{anyhow, Result};
+use serenity::{
+    all::{
+        ChannelId, CommandOptionType, CreateCommandOption, ResolvedOption, ResolvedValue, RoleId,
+        UserId,
+    },
+    utils::{parse_channel_mention, parse_role_mention, parse_user_mention},
+};
+
+/// The kind of value a command argument is parsed into.
+// Not every kind is used by a command yet.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgKind {
+    /// A user mention or a raw user Id.
+    User,
+    /// A channel mention or a raw channel Id.
+    Channel,
+    /// A role mention or a raw role Id.
+    Role,
+    /// A whole number.
+    Integer,
+    /// A duration such as `10s`, `5m` or `1h30m`.
+    Duration,
+    /// A single word or a "quoted string".
+    Text,
+    /// Everything that is left of the message, with its original spacing.
+    Rest,
+}
+
+/// Declares a single argument a command accepts.
+#[derive(Debug, Clone, Copy)]
+pub struct ArgSpec {
+    pub name: &'static str,
+    pub kind: ArgKind,
+    pub optional: bool,
+}
+
+impl ArgSpec {
+    pub const fn required(name: &'static str, kind: ArgKind) -> Self {
+        Self {
+            name,
+            kind,
+            optional: false,
+        }
+    }
+
+    pub const fn optional(name: &'static str, kind: ArgKind) -> Self {
+        Self {
+            name,
+            kind,
+            optional: true,
+        }
+    }
+
+    /// Builds the slash command option for this argument. Kinds that Discord
+    /// has no dedicated option type for are passed as strings and parsed
+    /// like message arguments.
+    pub fn slash_option(&self) -> CreateCommandOption {
+        let kind = match self.kind {
+            ArgKind::User => CommandOptionType::User,
+            ArgKind::Channel => CommandOptionType::Channel,
+            ArgKind::Role => CommandOptionType::Role,
+            ArgKind::Integer => CommandOptionType::Integer,
+            ArgKind::Duration | ArgKind::Text | ArgKind::Rest => CommandOptionType::String,
+        };
+
+        CreateCommandOption::new(kind, self.name, self.name).required(!self.optional)
+    }
+}
+
+/// A single parsed argument value.
+#[derive(Debug, Clone)]
+pub enum Arg {
+    User(UserId),
+    Channel(ChannelId),
+    Role(RoleId),
+    Integer(i64),
+    Duration(Duration),
+    Text(String),
+}
+
+/// The typed arguments of a command invocation, keyed by the names declared
+/// in the command's [`ArgSpec`]s.
+#[derive(Debug, Clone, Default)]
+pub struct Args {
+    values: HashMap<&'static str, Arg>,
+}
+
+impl Args {
+    /// Adds the given arguments, e.g. those of a subcommand, replacing
+    /// existing arguments with the same name.
+    pub fn merge(mut self, other: Args) -> Self {
+        self.values.extend(other.values);
+        self
+    }
+
+    pub fn user(&self, name: &str) -> Option<UserId> {
+        match self.values.get(name) {
+            Some(Arg::User(id)) => Some(*id),
+            _ => None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn channel(&self, name: &str) -> Option<ChannelId> {
+        match self.values.get(name) {
+            Some(Arg::Channel(id)) => Some(*id),
+            _ => None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn role(&self, name: &str) -> Option<RoleId> {
+        match self.values.get(name) {
+            Some(Arg::Role(id)) => Some(*id),
+            _ => None,
+        }
+    }
+
+    pub fn integer(&self, name: &str) -> Option<i64> {
+        match self.values.get(name) {
+            Some(Arg::Integer(n)) => Some(*n),
+            _ => None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn duration(&self, name: &str) -> Option<Duration> {
+        match self.values.get(name) {
+            Some(Arg::Duration(d)) => Some(*d),
+            _ => None,
+        }
+    }
+
+    pub fn text(&self, name: &str) -> Option<&str> {
+        match self.values.get(name) {
+            Some(Arg::Text(s)) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// A single token of the raw message together with its byte offset, so that
+/// the rest of the line can be sliced out of the original content.
+struct Token<'a> {
+    start: usize,
+    value: &'a str,
+}
+
+/// Splits the input on whitespace, keeping "quoted strings" together.
+/// The surrounding quotes are stripped from the token value.
+fn tokenize(input: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        if c == '"' {
+            chars.next();
+            let inner_start = start + 1;
+            let mut end = input.len();
+            for (idx, c) in chars.by_ref() {
+                if c == '"' {
+                    end = idx;
+                    break;
+                }
+            }
+            tokens.push(Token {
+                start,
+                value: &input[inner_start..end],
+            });
+        } else {
+            let mut end = input.len();
+            while let Some(&(idx, c)) = chars.peek() {
+                if c.is_whitespace() {
+                    end = idx;
+                    break;
+                }
+                chars.next();
+            }
+            tokens.push(Token {
+                start,
+                value: &input[start..end],
+            });
+        }
+    }
+
+    tokens
+}
+
+/// Parses a duration such as `30s`, `5m`, `2h`, `1d`, `1w` or a combination
+/// like `1h30m`. A bare number is treated as seconds.
+pub fn parse_duration(input: &str) -> Option<Duration> {
+    if let Ok(secs) = input.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let mut total = 0u64;
+    let mut number = String::new();
+
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let multiplier = match c.to_ascii_lowercase() {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 60 * 60 * 24,
+            'w' => 60 * 60 * 24 * 7,
+            _ => return None,
+        };
+        let value = number.parse::<u64>().ok()?;
+        total = total.checked_add(value.checked_mul(multiplier)?)?;
+        number.clear();
+    }
+
+    if !number.is_empty() || total == 0 {
+        return None;
+    }
+
+    Some(Duration::from_secs(total))
+}
+
+fn parse_id(input: &str) -> Option<u64> {
+    input.parse::<u64>().ok().filter(|id| *id != 0)
+}
+
+fn parse_value(kind: ArgKind, token: &str) -> Option<Arg> {
+    match kind {
+        ArgKind::User => parse_user_mention(token)
+            .or_else(|| parse_id(token).map(UserId::new))
+            .map(Arg::User),
+        ArgKind::Channel => parse_channel_mention(token)
+            .or_else(|| parse_id(token).map(ChannelId::new))
+            .map(Arg::Channel),
+        ArgKind::Role => parse_role_mention(token)
+            .or_else(|| parse_id(token).map(RoleId::new))
+            .map(Arg::Role),
+        ArgKind::Integer => token.parse::<i64>().ok().map(Arg::Integer),
+        ArgKind::Duration => parse_duration(token).map(Arg::Duration),
+        ArgKind::Text | ArgKind::Rest => Some(Arg::Text(token.to_string())),
+    }
+}
+
+/// Builds the usage signature for the given command name and arguments,
+/// e.g. `h!avatar [user]` or `h!prefix <action> [prefix]`.
+pub fn signature(prefix: &str, name: &str, specs: &[ArgSpec]) -> String {
+    let mut signature = format!("{prefix}{name}");
+    for spec in specs {
+        if spec.optional {
+            signature.push_str(&format!(" [{}]", spec.name));
+        } else {
+            signature.push_str(&format!(" <{}>", spec.name));
+        }
+    }
+    signature
+}
+
+/// Parses the raw arguments of a command according to its argument specs.
+///
+/// Optional arguments that don't match the next token are skipped and the
+/// token is tried against the following spec instead.
+///
+/// # Arguments
+/// * `input` - Everything after the command name, with its original casing.
+/// * `specs` - The arguments the command declares.
+///
+/// # Errors
+/// * If a required argument is missing or can't be parsed into its kind.
+/// * If tokens are left over after all arguments were parsed. If an optional
+///   argument was skipped because of the first leftover token, it is reported
+///   as invalid instead.
+pub fn parse_args(input: &str, specs: &[ArgSpec]) -> Result<Args> {
+    let tokens = tokenize(input);
+    let mut values = HashMap::new();
+    let mut idx = 0;
+    // The optional argument that was last skipped, and the token it failed on.
+    let mut skipped: Option<(&ArgSpec, usize)> = None;
+
+    for spec in specs {
+        let Some(token) = tokens.get(idx) else {
+            if spec.optional {
+                continue;
+            }
+            return Err(anyhow!("Missing argument `{}`", spec.name));
+        };
+
+        if spec.kind == ArgKind::Rest {
+            let rest = if tokens.len() - idx == 1 {
+                token.value
+            } else {
+                input[token.start..].trim_end()
+            };
+            values.insert(spec.name, Arg::Text(rest.to_string()));
+            idx = tokens.len();
+            continue;
+        }
+
+        match parse_value(spec.kind, token.value) {
+            Some(value) => {
+                values.insert(spec.name, value);
+                idx += 1;
+            }
+            None if spec.optional => skipped = Some((spec, idx)),
+            None => return Err(anyhow!("Invalid value for argument `{}`", spec.name)),
+        }
+    }
+
+    if let Some(token) = tokens.get(idx) {
+        return match skipped {
+            Some((spec, skipped_idx)) if skipped_idx == idx => {
+                Err(anyhow!("Invalid value for argument `{}`", spec.name))
+            }
+            _ => Err(anyhow!("Unexpected argument `{}`", token.value)),
+        };
+    }
+
+    Ok(Args { values })
+}
+
+/// Converts the options of a slash command into typed arguments according to
+/// the command's argument specs, so slash commands can share the handlers of
+/// message commands.
+///
+/// # Errors
+/// * If a required option is missing or can't be parsed into its kind.
+pub fn parse_options(options: &[ResolvedOption<'_>], specs: &[ArgSpec]) -> Result<Args> {
+    let mut values = HashMap::new();
+
+    for spec in specs {
+        let Some(option) = options.iter().find(|option| option.name == spec.name) else {
+            if spec.optional {
+                continue;
+            }
+            return Err(anyhow!("Missing argument `{}`", spec.name));
+        };
+
+        let value = match (&option.value, spec.kind) {
+            (ResolvedValue::User(user, _), ArgKind::User) => Some(Arg::User(user.id)),
+            (ResolvedValue::Channel(channel), ArgKind::Channel) => Some(Arg::Channel(channel.id)),
+            (ResolvedValue::Role(role), ArgKind::Role) => Some(Arg::Role(role.id)),
+            (ResolvedValue::Integer(n), ArgKind::Integer) => Some(Arg::Integer(*n)),
+            (ResolvedValue::String(s), kind) => parse_value(kind, s),
+            _ => None,
+        };
+
+        match value {
+            Some(value) => {
+                values.insert(spec.name, value);
+            }
+            None => return Err(anyhow!("Invalid value for argument `{}`", spec.name)),
+        }
+    }
+
+    Ok(Args { values })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AVATAR: &[ArgSpec] = &[ArgSpec::optional("user", ArgKind::User)];
+    const CONVERT: &[ArgSpec] = &[
+        ArgSpec::required("amount", ArgKind::Text),
+        ArgSpec::required("from", ArgKind::Text),
+        ArgSpec::optional("to", ArgKind::Text),
+    ];
+    const REST: &[ArgSpec] = &[
+        ArgSpec::required("action", ArgKind::Text),
+        ArgSpec::optional("query", ArgKind::Rest),
+    ];
+    const KINDS: &[ArgSpec] = &[
+        ArgSpec::required("channel", ArgKind::Channel),
+        ArgSpec::required("role", ArgKind::Role),
+        ArgSpec::required("count", ArgKind::Integer),
+        ArgSpec::optional("duration", ArgKind::Duration),
+    ];
+
+    fn values(input: &str) -> Vec<&str> {
+        tokenize(input).iter().map(|token| token.value).collect()
+    }
+
+    #[test]
+    fn tokenize_splits_on_whitespace() {
+        assert_eq!(values("  a  b\tc\n"), ["a", "b", "c"]);
+        assert!(values("   ").is_empty());
+    }
+
+    #[test]
+    fn tokenize_keeps_quoted_strings_together() {
+        assert_eq!(
+            values(r#"add "hello world" x"#),
+            ["add", "hello world", "x"]
+        );
+        assert_eq!(values(r#""""#), [""]);
+        assert_eq!(values(r#""unterminated quote"#), ["unterminated quote"]);
+    }
+
+    #[test]
+    fn tokenize_records_offsets() {
+        let tokens = tokenize(r#"a  "b c" d"#);
+        let starts = tokens.iter().map(|token| token.start).collect::<Vec<_>>();
+        assert_eq!(starts, [0, 3, 9]);
+    }
+
+    #[test]
+    fn parse_args_parses_users() {
+        let args = parse_args("<@123>", AVATAR).unwrap();
+        assert_eq!(args.user("user"), Some(UserId::new(123)));
+
+        let args = parse_args("456", AVATAR).unwrap();
+        assert_eq!(args.user("user"), Some(UserId::new(456)));
+
+        let args = parse_args("", AVATAR).unwrap();
+        assert_eq!(args.user("user"), None);
+    }
+
+    #[test]
+    fn parse_args_rejects_invalid_optional_args() {
+        let error = parse_args("bogus", AVATAR).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid value for argument `user`");
+
+        let error = parse_args("0", AVATAR).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid value for argument `user`");
+    }
+
+    #[test]
+    fn parse_args_rejects_leftover_tokens() {
+        let error = parse_args("<@1> <@2>", AVATAR).unwrap_err();
+        assert_eq!(error.to_string(), "Unexpected argument `<@2>`");
+
+        let error = parse_args("5 usd eur gbp", CONVERT).unwrap_err();
+        assert_eq!(error.to_string(), "Unexpected argument `gbp`");
+    }
+
+    #[test]
+    fn parse_args_reports_missing_required_args() {
+        let error = parse_args("5", CONVERT).unwrap_err();
+        assert_eq!(error.to_string(), "Missing argument `from`");
+
+        let args = parse_args("5 usd", CONVERT).unwrap();
+        assert_eq!(args.text("amount"), Some("5"));
+        assert_eq!(args.text("from"), Some("usd"));
+        assert_eq!(args.text("to"), None);
+    }
+
+    #[test]
+    fn parse_args_keeps_the_rest_verbatim() {
+        let args = parse_args("add  Hello   World  ", REST).unwrap();
+        assert_eq!(args.text("action"), Some("add"));
+        assert_eq!(args.text("query"), Some("Hello   World"));
+
+        let args = parse_args(r#"add "quoted""#, REST).unwrap();
+        assert_eq!(args.text("query"), Some("quoted"));
+
+        let args = parse_args("list", REST).unwrap();
+        assert_eq!(args.text("query"), None);
+    }
+
+    #[test]
+    fn parse_args_parses_channels_roles_and_integers() {
+        let args = parse_args("<#1> <@&2> -3 1h", KINDS).unwrap();
+        assert_eq!(args.channel("channel"), Some(ChannelId::new(1)));
+        assert_eq!(args.role("role"), Some(RoleId::new(2)));
+        assert_eq!(args.integer("count"), Some(-3));
+        assert_eq!(args.duration("duration"), Some(Duration::from_secs(3600)));
+
+        let args = parse_args("10 20 30", KINDS).unwrap();
+        assert_eq!(args.channel("channel"), Some(ChannelId::new(10)));
+        assert_eq!(args.role("role"), Some(RoleId::new(20)));
+        assert_eq!(args.integer("count"), Some(30));
+        assert_eq!(args.duration("duration"), None);
+    }
+
+    #[test]
+    fn parse_args_rejects_invalid_channels_roles_and_integers() {
+        let error = parse_args("<@1> 2 3", KINDS).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid value for argument `channel`");
+
+        let error = parse_args("1 <#2> 3", KINDS).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid value for argument `role`");
+
+        let error = parse_args("1 2 3.5", KINDS).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid value for argument `count`");
+
+        let error = parse_args("1 2 3 soon", KINDS).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid value for argument `duration`");
+    }
+
+    #[test]
+    fn parse_duration_accepts_units_and_combinations() {
+        assert_eq!(parse_duration("90"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("5M"), Some(Duration::from_secs(5 * 60)));
+        assert_eq!(parse_duration("1h30m"), Some(Duration::from_secs(90 * 60)));
+        assert_eq!(
+            parse_duration("1w1d"),
+            Some(Duration::from_secs(8 * 24 * 60 * 60))
+        );
+    }
+
+    #[test]
+    fn parse_duration_rejects_invalid_durations() {
+        for input in ["", "0s", "h", "1h30", "5x", "1.5h", "-5m"] {
+            assert_eq!(parse_duration(input), None, "{input}");
+        }
+        assert_eq!(parse_duration(&format!("{}w", u64::MAX)), None);
+    }
+
+    #[test]
+    fn merge_replaces_arguments_with_the_same_name() {
+        let args = parse_args("show 42", REST)
+            .unwrap()
+            .merge(parse_args("43", &[ArgSpec::required("query", ArgKind::Integer)]).unwrap());
+        assert_eq!(args.text("action"), Some("show"));
+        assert_eq!(args.integer("query"), Some(43));
+        assert_eq!(args.text("query"), None);
+    }
+}
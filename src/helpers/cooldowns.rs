@@ -0,0 +1,284 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::{self, Display},
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use super::utils::format_duration;
+
+/// Once this many buckets are tracked, buckets whose cooldown expired are
+/// dropped so the map doesn't grow with every user that ever used a command.
+const SWEEP_THRESHOLD: usize = 10_000;
+
+/// Who shares the uses of a command with a cooldown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CooldownBucket {
+    /// Every user has their own uses.
+    User,
+    /// All users in a channel share their uses.
+    Channel,
+    /// All users in a guild share their uses. In DMs, the DM channel is used
+    /// instead.
+    Guild,
+}
+
+/// Declares how often a command can be used, e.g. 3 times every 10 seconds
+/// per user. Up to `uses` uses are allowed in a burst, after that the oldest
+/// use has to be `seconds` seconds ago before the command can be used again.
+#[derive(Debug, Clone, Copy)]
+pub struct Cooldown {
+    pub bucket: CooldownBucket,
+    pub uses: usize,
+    pub seconds: u64,
+}
+
+impl Cooldown {
+    pub const fn per_user(uses: usize, seconds: u64) -> Self {
+        Self {
+            bucket: CooldownBucket::User,
+            uses,
+            seconds,
+        }
+    }
+
+    pub const fn per_channel(uses: usize, seconds: u64) -> Self {
+        Self {
+            bucket: CooldownBucket::Channel,
+            uses,
+            seconds,
+        }
+    }
+
+    pub const fn per_guild(uses: usize, seconds: u64) -> Self {
+        Self {
+            bucket: CooldownBucket::Guild,
+            uses,
+            seconds,
+        }
+    }
+
+    fn period(&self) -> Duration {
+        Duration::from_secs(self.seconds)
+    }
+}
+
+impl Display for Cooldown {
+    /// Formats the cooldown for the help command, e.g. `3 uses every 10s per
+    /// user`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bucket = match self.bucket {
+            CooldownBucket::User => "user",
+            CooldownBucket::Channel => "channel",
+            CooldownBucket::Guild => "server",
+        };
+        let uses = if self.uses == 1 { "use" } else { "uses" };
+        let seconds = i64::try_from(self.seconds).unwrap_or(i64::MAX);
+
+        write!(
+            f,
+            "{} {uses} every {} per {bucket}",
+            self.uses,
+            format_duration(seconds)
+        )
+    }
+}
+
+/// The result of trying to use a command with a cooldown.
+pub enum CooldownCheck {
+    /// The use was recorded and the command can run.
+    Ready,
+    /// The command is on cooldown. `notify` is only set for the first refused
+    /// use of a cooldown, so the author isn't told about it over and over.
+    Limited { retry_after: Duration, notify: bool },
+}
+
+/// The recent uses of a command in a single bucket.
+struct BucketState {
+    uses: VecDeque<Instant>,
+    period: Duration,
+    notified: bool,
+}
+
+/// The recent uses of all commands with cooldowns, keyed by the command, the
+/// kind of bucket and the Id of the user, channel or guild.
+#[derive(Default)]
+pub struct Cooldowns {
+    buckets: Mutex<HashMap<(&'static str, CooldownBucket, u64), BucketState>>,
+}
+
+impl Cooldowns {
+    /// Records a use of the command in the bucket with the given Id, unless
+    /// the command is on cooldown there.
+    pub fn hit(
+        &self,
+        command: &'static str,
+        cooldown: &Cooldown,
+        id: u64,
+        now: Instant,
+    ) -> CooldownCheck {
+        let period = cooldown.period();
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if buckets.len() >= SWEEP_THRESHOLD {
+            buckets.retain(|_, state| {
+                state
+                    .uses
+                    .back()
+                    .is_some_and(|last| now.duration_since(*last) < state.period)
+            });
+        }
+
+        let state = buckets
+            .entry((command, cooldown.bucket, id))
+            .or_insert_with(|| BucketState {
+                uses: VecDeque::with_capacity(cooldown.uses),
+                period,
+                notified: false,
+            });
+
+        while state
+            .uses
+            .front()
+            .is_some_and(|used| now.duration_since(*used) >= period)
+        {
+            state.uses.pop_front();
+        }
+
+        if state.uses.len() < cooldown.uses {
+            state.uses.push_back(now);
+            state.notified = false;
+            return CooldownCheck::Ready;
+        }
+
+        let retry_after = state.uses.front().map_or(Duration::ZERO, |oldest| {
+            period - now.duration_since(*oldest)
+        });
+        let notify = !state.notified;
+        state.notified = true;
+
+        CooldownCheck::Limited {
+            retry_after,
+            notify,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWICE_PER_TEN_SECONDS: Cooldown = Cooldown::per_user(2, 10);
+
+    fn is_ready(check: &CooldownCheck) -> bool {
+        matches!(check, CooldownCheck::Ready)
+    }
+
+    #[test]
+    fn allows_a_burst_of_uses() {
+        let cooldowns = Cooldowns::default();
+        let now = Instant::now();
+
+        assert!(is_ready(&cooldowns.hit(
+            "test",
+            &TWICE_PER_TEN_SECONDS,
+            1,
+            now
+        )));
+        assert!(is_ready(&cooldowns.hit(
+            "test",
+            &TWICE_PER_TEN_SECONDS,
+            1,
+            now
+        )));
+
+        let CooldownCheck::Limited { retry_after, .. } = cooldowns.hit(
+            "test",
+            &TWICE_PER_TEN_SECONDS,
+            1,
+            now + Duration::from_secs(4),
+        ) else {
+            panic!("the third use should be limited");
+        };
+        assert_eq!(retry_after, Duration::from_secs(6));
+    }
+
+    #[test]
+    fn buckets_are_separate() {
+        let cooldowns = Cooldowns::default();
+        let now = Instant::now();
+
+        for _ in 0..2 {
+            cooldowns.hit("test", &TWICE_PER_TEN_SECONDS, 1, now);
+        }
+
+        assert!(is_ready(&cooldowns.hit(
+            "test",
+            &TWICE_PER_TEN_SECONDS,
+            2,
+            now
+        )));
+        assert!(is_ready(&cooldowns.hit(
+            "other",
+            &TWICE_PER_TEN_SECONDS,
+            1,
+            now
+        )));
+        assert!(is_ready(&cooldowns.hit(
+            "test",
+            &Cooldown::per_channel(2, 10),
+            1,
+            now
+        )));
+    }
+
+    #[test]
+    fn uses_expire_after_the_period() {
+        let cooldowns = Cooldowns::default();
+        let now = Instant::now();
+
+        cooldowns.hit("test", &TWICE_PER_TEN_SECONDS, 1, now);
+        cooldowns.hit(
+            "test",
+            &TWICE_PER_TEN_SECONDS,
+            1,
+            now + Duration::from_secs(5),
+        );
+
+        let later = now + Duration::from_secs(10);
+        assert!(is_ready(&cooldowns.hit(
+            "test",
+            &TWICE_PER_TEN_SECONDS,
+            1,
+            later
+        )));
+        assert!(!is_ready(&cooldowns.hit(
+            "test",
+            &TWICE_PER_TEN_SECONDS,
+            1,
+            later
+        )));
+    }
+
+    #[test]
+    fn notifies_once_per_cooldown() {
+        let cooldowns = Cooldowns::default();
+        let now = Instant::now();
+        let notifies = |at: Instant| match cooldowns.hit("test", &TWICE_PER_TEN_SECONDS, 1, at) {
+            CooldownCheck::Ready => None,
+            CooldownCheck::Limited { notify, .. } => Some(notify),
+        };
+
+        assert_eq!(notifies(now), None);
+        assert_eq!(notifies(now), None);
+        assert_eq!(notifies(now), Some(true));
+        assert_eq!(notifies(now + Duration::from_secs(1)), Some(false));
+        assert_eq!(notifies(now + Duration::from_secs(2)), Some(false));
+
+        // A successful use starts a new cooldown, which is announced again.
+        let later = now + Duration::from_secs(10);
+        assert_eq!(notifies(later), None);
+        assert_eq!(notifies(later), None);
+        assert_eq!(notifies(later), Some(true));
+    }
+}
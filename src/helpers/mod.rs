@@ -1,2 +1,5 @@
+pub mod args;
+pub mod cooldowns;
+pub mod paginate;
 pub mod types;
 pub mod utils;
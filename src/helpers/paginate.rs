@@ -0,0 +1,104 @@
+use anyhow::Result;
+use serenity::{
+    all::{
+        ButtonStyle, CreateActionRow, CreateButton, CreateEmbedFooter, CreateInteractionResponse,
+        CreateInteractionResponseMessage, EditMessage,
+    },
+    builder::CreateEmbed,
+};
+use tokio::time::Duration;
+
+use super::types::CommandData;
+
+/// How long the navigation buttons stay active after the last interaction.
+const PAGINATION_TIMEOUT: Duration = Duration::from_secs(120);
+
+fn navigation_buttons(page: usize, total: usize) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new("page_prev")
+            .label("◀")
+            .style(ButtonStyle::Secondary)
+            .disabled(page == 0),
+        CreateButton::new("page_next")
+            .label("▶")
+            .style(ButtonStyle::Secondary)
+            .disabled(page + 1 >= total),
+    ])]
+}
+
+/// Splits the given lines into chunks of `per_page` lines each, so they can be
+/// used as the descriptions of the pages passed to [`paginate`].
+pub fn chunk_lines(lines: &[String], per_page: usize) -> Vec<String> {
+    lines
+        .chunks(per_page.max(1))
+        .map(|chunk| chunk.join("\n"))
+        .collect()
+}
+
+/// Sends the given embeds as a single message that can be paged through with
+/// buttons. Only the user who invoked the command can use the buttons.
+/// If there is only one page, it is sent without any buttons.
+///
+/// # Arguments
+/// * `data` - The data of the command that is replying.
+/// * `pages` - The embeds to page through.
+///
+/// # Errors
+/// * If sending or editing the message fails.
+pub async fn paginate(data: &CommandData<'_>, pages: Vec<CreateEmbed>) -> Result<()> {
+    let total = pages.len();
+    let pages = pages
+        .into_iter()
+        .enumerate()
+        .map(|(idx, embed)| {
+            embed.footer(CreateEmbedFooter::new(format!(
+                "Page {}/{}",
+                idx + 1,
+                total
+            )))
+        })
+        .collect::<Vec<_>>();
+
+    let Some(first) = pages.first() else {
+        return Ok(());
+    };
+
+    if total == 1 {
+        data.reply_embed(first.clone()).await?;
+        return Ok(());
+    }
+
+    let mut page = 0;
+    let mut sent = data
+        .reply_embed_with_components(first.clone(), navigation_buttons(page, total))
+        .await?;
+
+    while let Some(interaction) = sent
+        .await_component_interaction(&data.ctx.shard)
+        .author_id(data.author().id)
+        .timeout(PAGINATION_TIMEOUT)
+        .await
+    {
+        match interaction.data.custom_id.as_str() {
+            "page_prev" => page = page.saturating_sub(1),
+            "page_next" => page = (page + 1).min(total - 1),
+            _ => continue,
+        }
+
+        interaction
+            .create_response(
+                data.ctx,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::default()
+                        .embed(pages[page].clone())
+                        .components(navigation_buttons(page, total)),
+                ),
+            )
+            .await?;
+    }
+
+    sent.edit(data.ctx, EditMessage::default().components(vec![]))
+        .await?;
+
+    Ok(())
+}
@@ -1,41 +1,330 @@
-use std::collections::HashMap;
-use crate::db::models::Status;
+use std::{
+    collections::HashMap,
+    sync::{atomic::AtomicBool, Arc, PoisonError, RwLock as SyncRwLock},
+};
 
+use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use serenity::{all::UserId, model::prelude::Message, prelude::Context};
+use serenity::{
+    all::{
+        Attachment, Channel, ChannelId, CommandDataOptionValue, CommandInteraction,
+        CreateActionRow, CreateAllowedMentions, CreateInteractionResponseFollowup, CreateMessage,
+        GuildId, Permissions, User, UserId,
+    },
+    builder::CreateEmbed,
+    model::prelude::Message,
+    prelude::Context,
+};
 use tokio::sync::RwLock;
 
-use crate::config::Config;
+use super::{args::Args, cooldowns::Cooldowns};
+use crate::{
+    apis::{exchange::ExchangeClient, imgur::ImgurClient, reddit::RedditClient},
+    config::Config,
+    db::{models::Status, repositories::Database},
+};
 
 pub type StatusVec = RwLock<Vec<Status>>;
-pub type PrefixMap = RwLock<HashMap<String, String>>;
+/// The current config. Reloading swaps in a new config as a whole, so a
+/// command that took a snapshot keeps seeing a consistent config.
+pub type SharedConfig = SyncRwLock<Arc<Config>>;
+pub type PrefixMap = RwLock<HashMap<String, Vec<String>>>;
+/// Maps a character to all the strings it can be translated to in leet speak.
+pub type LeetMap = HashMap<char, Vec<String>>;
 
-#[allow(dead_code)]
-pub struct MessageCommandData<'a> {
+/// Where a command was invoked from, either a prefixed message or a slash
+/// command interaction.
+#[derive(Clone, Copy)]
+pub enum CommandSource<'a> {
+    Message(&'a Message),
+    Interaction(&'a CommandInteraction),
+}
+
+impl CommandSource<'_> {
+    pub fn author(&self) -> &User {
+        match self {
+            CommandSource::Message(msg) => &msg.author,
+            CommandSource::Interaction(interaction) => &interaction.user,
+        }
+    }
+
+    pub fn channel_id(&self) -> ChannelId {
+        match self {
+            CommandSource::Message(msg) => msg.channel_id,
+            CommandSource::Interaction(interaction) => interaction.channel_id,
+        }
+    }
+
+    pub fn guild_id(&self) -> Option<GuildId> {
+        match self {
+            CommandSource::Message(msg) => msg.guild_id,
+            CommandSource::Interaction(interaction) => interaction.guild_id,
+        }
+    }
+
+    /// The files attached to the message. Slash commands never have any.
+    pub fn attachments(&self) -> &[Attachment] {
+        match self {
+            CommandSource::Message(msg) => &msg.attachments,
+            CommandSource::Interaction(_) => &[],
+        }
+    }
+
+    /// The text the command was invoked with. For slash commands this is
+    /// rebuilt from the command name and its options.
+    pub fn content(&self) -> String {
+        match self {
+            CommandSource::Message(msg) => msg.content.clone(),
+            CommandSource::Interaction(interaction) => {
+                let mut content = format!("/{}", interaction.data.name);
+                for option in &interaction.data.options {
+                    let value = match &option.value {
+                        CommandDataOptionValue::String(s) => s.clone(),
+                        CommandDataOptionValue::Integer(n) => n.to_string(),
+                        CommandDataOptionValue::Number(n) => n.to_string(),
+                        CommandDataOptionValue::Boolean(b) => b.to_string(),
+                        CommandDataOptionValue::User(id) => id.to_string(),
+                        CommandDataOptionValue::Channel(id) => id.to_string(),
+                        CommandDataOptionValue::Role(id) => id.to_string(),
+                        _ => "?".to_string(),
+                    };
+                    content.push_str(&format!(" {}:{}", option.name, value));
+                }
+                content
+            }
+        }
+    }
+
+    /// The guild permissions of the author, or `None` outside of guilds.
+    pub async fn member_permissions(&self, ctx: &Context) -> Result<Option<Permissions>> {
+        match self {
+            CommandSource::Message(msg) => match msg.guild_id {
+                Some(_) => Ok(Some(msg.member(ctx).await?.permissions(&ctx.cache)?)),
+                None => Ok(None),
+            },
+            CommandSource::Interaction(interaction) => Ok(interaction
+                .member
+                .as_ref()
+                .and_then(|member| member.permissions)),
+        }
+    }
+
+    /// Checks if the command was used in an age-restricted channel. Threads
+    /// inherit the setting of their parent channel, DMs are never
+    /// age-restricted.
+    pub async fn is_nsfw_channel(&self, ctx: &Context) -> Result<bool> {
+        let Channel::Guild(channel) = self.channel_id().to_channel(ctx).await? else {
+            return Ok(false);
+        };
+
+        if channel.thread_metadata.is_some() {
+            if let Some(parent_id) = channel.parent_id {
+                if let Channel::Guild(parent) = parent_id.to_channel(ctx).await? {
+                    return Ok(parent.nsfw);
+                }
+            }
+        }
+
+        Ok(channel.nsfw)
+    }
+
+    /// Replies with a plain text message.
+    pub async fn reply(&self, ctx: &Context, content: impl Into<String>) -> Result<Message> {
+        self.send(ctx, Some(content.into()), None, Vec::new(), None)
+            .await
+    }
+
+    /// Sends a reply to wherever the command came from. Slash commands are
+    /// deferred before they are run, so their replies are sent as followups.
+    /// Without `allowed_mentions`, Discord's defaults apply and all mentions
+    /// in the content ping.
+    pub async fn send(
+        &self,
+        ctx: &Context,
+        content: Option<String>,
+        embed: Option<CreateEmbed>,
+        components: Vec<CreateActionRow>,
+        allowed_mentions: Option<CreateAllowedMentions>,
+    ) -> Result<Message> {
+        let message = match self {
+            CommandSource::Message(msg) => {
+                let mut builder = CreateMessage::default().components(components);
+                if let Some(content) = content {
+                    builder = builder.content(content);
+                }
+                if let Some(embed) = embed {
+                    builder = builder.embed(embed);
+                }
+                if let Some(allowed_mentions) = allowed_mentions {
+                    builder = builder.allowed_mentions(allowed_mentions);
+                }
+                msg.channel_id.send_message(ctx, builder).await?
+            }
+            CommandSource::Interaction(interaction) => {
+                let mut builder =
+                    CreateInteractionResponseFollowup::default().components(components);
+                if let Some(content) = content {
+                    builder = builder.content(content);
+                }
+                if let Some(embed) = embed {
+                    builder = builder.embed(embed);
+                }
+                if let Some(allowed_mentions) = allowed_mentions {
+                    builder = builder.allowed_mentions(allowed_mentions);
+                }
+                interaction.create_followup(ctx, builder).await?
+            }
+        };
+
+        Ok(message)
+    }
+}
+
+pub struct CommandData<'a> {
     pub ctx: &'a Context,
-    pub msg: &'a Message,
-    pub content: Vec<String>,
-    pub command: String,
+    pub source: CommandSource<'a>,
+    pub args: Args,
+    /// The reaction command of messages like `$hug`, without the `$`.
     pub react_cmd: Option<String>,
-    pub sub_cmd: Option<String>,
-    pub handler: &'a Handler<'a>,
+    pub handler: &'a Handler,
     pub prefix: String,
+    /// Whether NSFW content may be posted in response to this command. Only
+    /// ever true for commands that post content in age-restricted channels
+    /// of guilds that allow it.
+    pub allow_nsfw: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl CommandData<'_> {
+    pub fn author(&self) -> &User {
+        self.source.author()
+    }
+
+    pub fn guild_id(&self) -> Option<GuildId> {
+        self.source.guild_id()
+    }
+
+    /// Replies with a plain text message.
+    pub async fn reply(&self, content: impl Into<String>) -> Result<Message> {
+        self.source.reply(self.ctx, content).await
+    }
+
+    /// Replies with a plain text message that doesn't ping anyone, for
+    /// replies echoing text of the author.
+    pub async fn reply_without_mentions(&self, content: impl Into<String>) -> Result<Message> {
+        self.source
+            .send(
+                self.ctx,
+                Some(content.into()),
+                None,
+                Vec::new(),
+                Some(CreateAllowedMentions::new()),
+            )
+            .await
+    }
+
+    /// Replies with a single embed.
+    pub async fn reply_embed(&self, embed: CreateEmbed) -> Result<Message> {
+        self.source
+            .send(self.ctx, None, Some(embed), Vec::new(), None)
+            .await
+    }
+
+    /// Replies with an embed showing fetched content, refusing to post it if
+    /// it is marked NSFW and NSFW content isn't allowed here. Every command
+    /// posting external content should go through this.
+    pub async fn reply_content(&self, embed: CreateEmbed, nsfw: bool) -> Result<Message> {
+        if nsfw && !self.allow_nsfw {
+            return self
+                .reply("This content is marked NSFW and can only be posted in age-restricted channels of servers that allow it")
+                .await;
+        }
+
+        self.reply_embed(embed).await
+    }
+
+    /// Replies with a single embed and the given components, e.g. buttons.
+    pub async fn reply_embed_with_components(
+        &self,
+        embed: CreateEmbed,
+        components: Vec<CreateActionRow>,
+    ) -> Result<Message> {
+        self.source
+            .send(self.ctx, None, Some(embed), components, None)
+            .await
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct Owners {
     pub primary: UserId,
     pub secondary: Vec<UserId>,
 }
 
+impl Owners {
+    /// Checks if the given user is either the primary or a secondary owner.
+    pub fn contains(&self, user_id: UserId) -> bool {
+        self.primary == user_id || self.secondary.contains(&user_id)
+    }
+}
+
 /// Handler contains the data necessary to run the bot. This includes the start
-/// time, the configuration, the database client, the statuses, and the
-/// prefixes.
-pub struct Handler<'a> {
+/// time, the configuration, the database client, the statuses, the prefixes,
+/// the leet speak translations and the API clients.
+pub struct Handler {
     pub start_time: DateTime<Utc>,
-    pub config: Config<'a>,
-    pub db_pool: sqlx::SqlitePool,
+    pub config: SharedConfig,
+    pub db: Box<dyn Database>,
     pub statuses: StatusVec,
     pub prefixes: PrefixMap,
+    pub leet: LeetMap,
+    pub reddit: RedditClient,
+    pub exchange: ExchangeClient,
+    pub imgur: ImgurClient,
+    pub cooldowns: Cooldowns,
+    /// Set once the background loops were started. `ready` fires again
+    /// whenever the bot has to identify again, which mustn't start them a
+    /// second time.
+    pub loops_started: AtomicBool,
+}
+
+impl Handler {
+    /// Returns a snapshot of the current config.
+    pub fn config(&self) -> Arc<Config> {
+        self.config
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+#[cfg(test)]
+impl Handler {
+    /// A handler with a minimal config and nothing loaded yet, using the
+    /// given database.
+    pub fn for_tests(db: impl Database + 'static) -> Self {
+        let config = Config::from_toml(
+            r#"
+            bot_token = "token"
+            bot_owner = 1
+            log_channel = 1
+            "#,
+            &[],
+        )
+        .expect("the test config is valid");
+
+        Self {
+            start_time: Utc::now(),
+            config: SyncRwLock::new(Arc::new(config)),
+            db: Box::new(db),
+            statuses: RwLock::default(),
+            prefixes: RwLock::default(),
+            leet: LeetMap::new(),
+            reddit: RedditClient::new(),
+            exchange: ExchangeClient::new(),
+            imgur: ImgurClient::new(),
+            cooldowns: Cooldowns::default(),
+            loops_started: AtomicBool::new(false),
+        }
+    }
 }
@@ -1,122 +1,170 @@
 #![allow(clippy::unreadable_literal)]
 
-use std::env;
+use std::{
+    env,
+    sync::{Arc, PoisonError},
+};
 
 use anyhow::{anyhow, Result};
-use chrono::{format::strftime::StrftimeItems, Utc};
+use chrono::{format::strftime::StrftimeItems, DateTime, Utc};
 use rand::{seq::SliceRandom, thread_rng, Rng};
 use serenity::{
-    all::{ActivityData, UserId},
+    all::{ActivityData, Command as SerenityCommand},
     model::{
-        prelude::{ChannelId, GuildId, Message},
+        prelude::{ChannelId, GuildId},
         user::User,
     },
     prelude::*,
 };
-use tokio::time::{sleep, Duration};
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    time::{sleep, Duration},
+};
 
-use super::types::{Handler, MessageCommandData, StatusVec};
-use crate::db::models::StatusType;
+use super::types::{CommandData, CommandSource, Handler, StatusVec};
+use crate::{
+    commands::slash_commands,
+    config::{Config, Integration},
+    db::{
+        backup::create_backup,
+        models::{ErrorLog, Status, StatusType},
+    },
+};
+
+/// How often a disabled backup schedule checks if it was enabled again.
+const BACKUP_DISABLED_RECHECK: Duration = Duration::from_secs(60);
 
 /// Logs an error to the console and to the error channel.
 /// Also saves it to the database.
 ///
 /// # Arguments
-/// * `message` - The message that caused the error.
+/// * `source` - The message or interaction that caused the error.
 /// * `error` - The error that occurred.
 /// * `ctx` - The context of the message.
 /// * `handler` - The event handler of the bot.
 ///
-/// TODO: Add database logging.
+/// # Errors
+/// * If saving the error to the database fails. The error is still sent to
+///   the error channel in that case.
+/// * If sending the error to the error channel fails.
+///
+/// # Returns
+/// The Id of the stored error, so it can be shown to the user.
 pub async fn error_log(
-    message: &Message,
+    source: CommandSource<'_>,
     error: &anyhow::Error,
     ctx: &Context,
-    handler: &Handler<'_>,
-) -> Result<()> {
+    handler: &Handler,
+) -> Result<i64> {
+    let now = Utc::now();
     let date_format = StrftimeItems::new("%d/%m/%Y %H:%M:%S UTC");
-    let current_time = Utc::now().format_with_items(date_format).to_string();
+    let current_time = now.format_with_items(date_format).to_string();
 
-    let error_channel = message
-        .channel_id
-        .name(&ctx)
-        .await
-        .unwrap_or("Unknown".into());
+    let channel_id = source.channel_id();
+    let command = source.content();
+
+    let error_channel = channel_id.name(&ctx).await.unwrap_or("Unknown".into());
 
-    let guild_name = match message.guild(&ctx.cache) {
+    let guild_name = match source.guild_id().and_then(|id| ctx.cache.guild(id)) {
         Some(guild) => guild.name.clone(),
         None => "Direct Message".to_string(),
     };
 
-    let guild_id = match message.guild_id {
+    let guild_id = match source.guild_id() {
         Some(id) => id.to_string(),
         None => "Unknown".to_string(),
     };
 
-    let (user_name, user_id) = (&message.author.name, message.author.id);
+    let (user_name, user_id) = (&source.author().name, source.author().id);
 
-    let error_msg = String::new()
-        + &format!("An Error occurred on {}\n", &current_time)
+    let log = String::new()
         + &format!("**Server:** {} - {}\n", &guild_name, &guild_id)
         + &format!("**Room:** {}\n", &error_channel)
         + &format!("**User:** {} - {}\n", &user_name, &user_id)
-        + &format!("**Command used:** {}\n", message.content)
+        + &format!("**Command used:** {}\n", &command)
         + &format!("**Error:** {}", &error);
 
+    let server = source.guild_id().map(|id| id.to_string());
+    let channel = channel_id.to_string();
+    let user = user_id.to_string();
+    let stack = format!("{error:?}");
+    let timestamp = now.timestamp();
+    let error_text = error.to_string();
+
+    let error_id = handler
+        .db
+        .insert_error(&ErrorLog {
+            id: 0,
+            server,
+            channel,
+            user,
+            command: Some(command.clone()),
+            stack: Some(stack),
+            timestamp: Some(timestamp),
+            log: Some(log.clone()),
+            error: Some(error_text),
+        })
+        .await;
+
+    let error_id_text = match &error_id {
+        Ok(id) => id.to_string(),
+        Err(e) => {
+            error!("Failed to save error to the database, {e}");
+            "Unknown".to_string()
+        }
+    };
+
+    let error_msg = String::new()
+        + &format!("An Error occurred on {}\n", &current_time)
+        + &format!("**Error Id:** {}\n", &error_id_text)
+        + &log;
+
     error!("An Error occurred on {}", &current_time);
+    error!("Error Id: {}", &error_id_text);
     error!("Server: {} - {}", &guild_name, &guild_id);
     error!("Room: {}", &error_channel);
     error!("User: {} - {}", &user_name, &user_id);
-    error!("Command used: {}", message.content);
+    error!("Command used: {}", &command);
     error!("Error: {}", &error);
 
-    let error_channel = if handler
-        .config
-        .dev_channels
-        .contains(&message.channel_id.into())
-    {
-        message.channel_id
+    let error_channel = if handler.config().dev_channels.contains(&channel_id.into()) {
+        channel_id
     } else {
-        ChannelId::new(handler.config.log_channel)
+        ChannelId::new(handler.config().log_channel)
     };
 
     error_channel.say(&ctx.http, &error_msg).await?;
 
-    Ok(())
+    error_id
 }
 
-/// Parses a user from the message content at the given index.
-/// If no user is found, the author of the message is returned.
-/// If the user is not found, an error is returned.
+/// Fetches the user passed as the argument with the given name.
+/// If the argument wasn't given, the author of the command is returned.
 ///
 /// # Arguments
-/// * `data` - The message command data.
-/// * `idx` - The index of the user in the message content.
+/// * `data` - The command data.
+/// * `name` - The name of the user argument.
 ///
 /// # Errors
 /// * If the user is not found.
-/// * If the user ID is not a valid u64.
 ///
 /// # Returns
 /// The target user.
-pub async fn parse_target_user<'a>(data: &MessageCommandData<'a>, idx: usize) -> Result<User> {
-    let user = if data.content.get(idx).is_some() {
-        let user_id = data.content[idx].replace("<@", "").replace('>', "");
-        let user_id = user_id
-            .parse::<u64>()
-            .map_err(|_| anyhow!("Invalid User Id"))?;
-        data.ctx
+pub async fn parse_target_user<'a>(data: &CommandData<'a>, name: &str) -> Result<User> {
+    let user = match data.args.user(name) {
+        Some(user_id) => data
+            .ctx
             .http
-            .get_user(UserId::from(user_id))
+            .get_user(user_id)
             .await
-            .map_err(|_| anyhow!("User not found"))?
-    } else {
-        data.msg.author.clone()
+            .map_err(|_| anyhow!("User not found"))?,
+        None => data.author().clone(),
     };
     Ok(user)
 }
 
-/// Registers the prefix for the guild in the database and in the prefixes map
+/// Registers the default prefix for the guild in the database and in the
+/// prefixes map
 ///
 /// # Arguments
 ///
@@ -130,43 +178,314 @@ pub async fn parse_target_user<'a>(data: &MessageCommandData<'a>, idx: usize) ->
 ///
 /// # Errors
 /// * If inserting the prefix into the database fails
-pub async fn register_prefix(guild_id: GuildId, handler: &Handler<'_>) -> Result<String> {
+pub async fn register_prefix(guild_id: GuildId, handler: &Handler) -> Result<String> {
     let server_id = guild_id.to_string();
-    let prefix = String::from("h!");
+    let prefix = handler.config().default_prefix.clone();
 
-    sqlx::query!(
-        "INSERT INTO prefixes (server_id, prefiX) VALUES (?, ?)",
-        server_id,
-        prefix,
-    )
-    .execute(&handler.db_pool)
-    .await?;
+    handler.db.add_prefix(&server_id, &prefix).await?;
 
     handler
         .prefixes
         .write()
         .await
-        .insert(server_id.clone(), prefix);
+        .insert(server_id.clone(), vec![prefix]);
 
     Ok(server_id)
 }
 
-/// A function that takes a vector of statuses and a context
-/// and sets the bot's status to a random status from the vector every 5-15
-/// minutes.
-pub async fn start_status_loop(statuses: &StatusVec, ctx: Context) {
+/// Replaces all prefixes of the guild with the given one, both in the
+/// database and in the prefixes map.
+///
+/// The prefixes map stays write-locked until the database has been updated,
+/// so no message can observe a prefix that isn't persisted yet.
+///
+/// # Arguments
+///
+/// * `guild_id` - The Id of the guild to set the prefix for
+/// * `prefix` - The new prefix
+/// * `handler` - The Event Handler that dispatches the events
+///
+/// # Errors
+/// * If updating the prefixes in the database fails
+pub async fn set_prefix(guild_id: GuildId, prefix: &str, handler: &Handler) -> Result<()> {
+    let server_id = guild_id.to_string();
+    let mut prefixes = handler.prefixes.write().await;
+
+    handler.db.replace_prefixes(&server_id, prefix).await?;
+
+    prefixes.insert(server_id, vec![prefix.to_string()]);
+
+    Ok(())
+}
+
+/// Adds another prefix to the guild, both in the database and in the
+/// prefixes map.
+///
+/// # Arguments
+///
+/// * `guild_id` - The Id of the guild to add the prefix to
+/// * `prefix` - The prefix to add
+/// * `handler` - The Event Handler that dispatches the events
+///
+/// # Errors
+/// * If inserting the prefix into the database fails
+pub async fn add_prefix(guild_id: GuildId, prefix: &str, handler: &Handler) -> Result<()> {
+    let server_id = guild_id.to_string();
+    let mut prefixes = handler.prefixes.write().await;
+
+    handler.db.add_prefix(&server_id, prefix).await?;
+
+    let guild_prefixes = prefixes.entry(server_id).or_default();
+    if !guild_prefixes.iter().any(|p| p == prefix) {
+        guild_prefixes.push(prefix.to_string());
+    }
+
+    Ok(())
+}
+
+/// Removes a prefix from the guild, both in the database and in the prefixes
+/// map.
+///
+/// # Arguments
+///
+/// * `guild_id` - The Id of the guild to remove the prefix from
+/// * `prefix` - The prefix to remove
+/// * `handler` - The Event Handler that dispatches the events
+///
+/// # Returns
+///
+/// * `bool` - Whether the guild had the prefix
+///
+/// # Errors
+/// * If deleting the prefix from the database fails
+pub async fn remove_prefix(guild_id: GuildId, prefix: &str, handler: &Handler) -> Result<bool> {
+    let server_id = guild_id.to_string();
+    let mut prefixes = handler.prefixes.write().await;
+
+    let removed = handler.db.remove_prefix(&server_id, prefix).await?;
+
+    if let Some(guild_prefixes) = prefixes.get_mut(&server_id) {
+        guild_prefixes.retain(|p| p != prefix);
+    }
+
+    Ok(removed)
+}
+
+/// Checks if the guild allows NSFW content. Guilds without settings allow it,
+/// it is still only posted in age-restricted channels either way.
+///
+/// # Arguments
+///
+/// * `guild_id` - The Id of the guild to check
+/// * `handler` - The Event Handler that dispatches the events
+///
+/// # Errors
+/// * If reading the guild settings from the database fails
+pub async fn nsfw_enabled(guild_id: GuildId, handler: &Handler) -> Result<bool> {
+    let enabled = handler.db.nsfw_enabled(&guild_id.to_string()).await?;
+
+    Ok(enabled.unwrap_or(true))
+}
+
+/// Enables or disables NSFW content for the guild.
+///
+/// # Arguments
+///
+/// * `guild_id` - The Id of the guild to update
+/// * `enabled` - Whether NSFW content should be allowed
+/// * `handler` - The Event Handler that dispatches the events
+///
+/// # Errors
+/// * If updating the guild settings in the database fails
+pub async fn set_nsfw_enabled(guild_id: GuildId, enabled: bool, handler: &Handler) -> Result<()> {
+    handler
+        .db
+        .set_nsfw_enabled(&guild_id.to_string(), enabled)
+        .await
+}
+
+/// A function that takes the event handler and a context and sets the bot's
+/// status to a random active status every `status_interval_min` to
+/// `status_interval_max` seconds (5-15 minutes by default).
+///
+/// Statuses are picked weighted by their `weight`, and the vector is read
+/// again every time, so statuses added or removed while the bot is running
+/// are picked up without restarting the loop.
+pub async fn start_status_loop(handler: &Handler, ctx: Context) {
     loop {
-        let random_status = random_element_vec(&statuses.read().await);
+        let random_status = random_active_status(&handler.statuses, Utc::now()).await;
 
         if let Some(status) = random_status {
-            let activity = get_activity(&status.r#type, &status.status);
+            let text = render_status(&status.status, &ctx, handler);
+            let activity = get_activity(&status.r#type, &text);
             ctx.set_activity(Some(activity));
-            debug!("Set status to: {:?} {}", status.r#type, status.status);
+            debug!("Set status to: {:?} {}", status.r#type, text);
         } else {
-            error!("No statuses found in database");
+            warn!("No active statuses found in database");
+        }
+
+        let min = handler.config().status_interval_min;
+        let max = handler.config().status_interval_max.max(min);
+        sleep(Duration::from_secs(random_int_from_range(min, max))).await;
+    }
+}
+
+/// Reads the config again and swaps it in for all commands that run from now
+/// on. The current config is kept if the new one is invalid.
+///
+/// # Arguments
+///
+/// * `ctx` - The context, to register the slash commands again if the
+///   enabled integrations changed
+/// * `handler` - The Event Handler that dispatches the events
+///
+/// # Returns
+///
+/// The keys whose values changed.
+///
+/// # Errors
+/// * If the config can't be read or is invalid
+pub async fn reload_config(ctx: &Context, handler: &Handler) -> Result<Vec<&'static str>> {
+    let new_config = Config::load()?;
+    let enabled_before = enabled_integrations(&handler.config());
+
+    let changed = {
+        let mut config = handler
+            .config
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        let changed = config.changed_fields(&new_config);
+        *config = Arc::new(new_config);
+        changed
+    };
+
+    if changed.iter().any(|key| key.starts_with("reddit_")) {
+        handler.reddit.clear_token().await;
+    }
+
+    let config = handler.config();
+    config.report_missing_credentials();
+
+    if enabled_integrations(&config) != enabled_before {
+        register_slash_commands(ctx, &config).await;
+    }
+
+    Ok(changed)
+}
+
+fn enabled_integrations(config: &Config) -> Vec<Integration> {
+    Integration::ALL
+        .into_iter()
+        .filter(|integration| config.is_enabled(*integration))
+        .collect()
+}
+
+/// Registers the slash commands of all enabled commands, replacing the
+/// registered ones. Guild commands update instantly while global ones can
+/// take a while to propagate, so they are only registered globally in
+/// production.
+pub async fn register_slash_commands(ctx: &Context, config: &Config) {
+    let commands = slash_commands(config);
+
+    if is_indev() {
+        for guild_id in ctx.cache.guilds() {
+            if let Err(e) = guild_id.set_commands(&ctx.http, commands.clone()).await {
+                error!("Failed to register slash commands in guild {guild_id}: {e}");
+            }
+        }
+    } else if let Err(e) = SerenityCommand::set_global_commands(&ctx.http, commands).await {
+        error!("Failed to register global slash commands: {e}");
+    }
+}
+
+/// Reloads the config whenever the process receives a SIGHUP, logging what
+/// changed or why the new config was refused.
+pub async fn start_reload_signal_loop(handler: &Handler, ctx: Context) {
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            error!("Failed to listen for SIGHUP, config reloading via signal is disabled: {e}");
             return;
         }
-        sleep(Duration::from_secs(random_int_from_range(300, 900))).await; // 5-15 minutes
+    };
+
+    while hangups.recv().await.is_some() {
+        match reload_config(&ctx, handler).await {
+            Ok(changed) if changed.is_empty() => info!("Reloaded config, nothing changed"),
+            Ok(changed) => info!("Reloaded config, changed: {}", changed.join(", ")),
+            Err(e) => error!("Refused to reload config: {e}"),
+        }
+    }
+}
+
+/// Backs up the database every `backup_interval` hours, deleting backups
+/// exceeding `backup_retention`. The interval is read again after every
+/// backup, so it can be changed by reloading the config.
+pub async fn start_backup_loop(handler: &Handler) {
+    if !handler.db.supports_backups() {
+        info!("Scheduled backups are disabled, the database doesn't support them");
+        return;
+    }
+
+    loop {
+        let hours = handler.config().backup_interval;
+
+        if hours == 0 {
+            sleep(BACKUP_DISABLED_RECHECK).await;
+            continue;
+        }
+
+        sleep(Duration::from_secs(hours.saturating_mul(60 * 60))).await;
+
+        match create_backup(handler.db.as_ref(), &handler.config()).await {
+            Ok(path) => info!("Backed up the database to {}", path.display()),
+            Err(e) => error!("Failed to back up the database: {e}"),
+        }
+    }
+}
+
+/// Picks a random status out of the ones that are active at the given time,
+/// weighted by their `weight`.
+async fn random_active_status(statuses: &StatusVec, now: DateTime<Utc>) -> Option<Status> {
+    let statuses = statuses.read().await;
+    let active = statuses
+        .iter()
+        .filter(|status| status.is_active(now))
+        .collect::<Vec<_>>();
+
+    active
+        .choose_weighted(&mut thread_rng(), |status| status.weight)
+        .ok()
+        .map(|status| (*status).clone())
+}
+
+/// Fills in the template variables of a status text:
+///
+/// * `{servers}` - The number of servers the bot is in.
+/// * `{uptime}` - How long the bot has been running for.
+/// * `{prefix}` - The default prefix.
+pub fn render_status(template: &str, ctx: &Context, handler: &Handler) -> String {
+    let uptime = (Utc::now() - handler.start_time).num_seconds();
+
+    template
+        .replace("{servers}", &ctx.cache.guild_count().to_string())
+        .replace("{uptime}", &format_duration(uptime))
+        .replace("{prefix}", &handler.config().default_prefix)
+}
+
+/// Formats a number of seconds as a short human readable duration, e.g.
+/// `2d 3h 15m`.
+pub fn format_duration(total_seconds: i64) -> String {
+    let days = total_seconds / 86400;
+    let hours = total_seconds % 86400 / 3600;
+    let minutes = total_seconds % 3600 / 60;
+    let seconds = total_seconds % 60;
+
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{seconds}s"),
+        (0, 0, _) => format!("{minutes}m"),
+        (0, _, _) => format!("{hours}h {minutes}m"),
+        _ => format!("{days}d {hours}h {minutes}m"),
     }
 }
 
@@ -185,6 +504,39 @@ pub fn random_int_from_range(min: u64, max: u64) -> u64 {
     thread_rng().gen_range(min..=max)
 }
 
+/// Splits the text into chunks of at most `max_len` characters, preferring to
+/// split at whitespace so words stay intact.
+///
+/// # Example
+/// ```
+/// use helpers::utils;
+/// let chunks = utils::split_message("hello world", 8);
+/// assert_eq!(chunks, vec!["hello", "world"]);
+/// ```
+pub fn split_message(text: &str, max_len: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut rest = text.trim();
+
+    while rest.chars().count() > max_len {
+        let limit = rest
+            .char_indices()
+            .nth(max_len)
+            .map_or(rest.len(), |(idx, _)| idx);
+        let split_at = rest[..limit]
+            .rfind(char::is_whitespace)
+            .filter(|idx| *idx > 0)
+            .unwrap_or(limit);
+
+        chunks.push(rest[..split_at].trim_end().to_string());
+        rest = rest[split_at..].trim_start();
+    }
+
+    if !rest.is_empty() {
+        chunks.push(rest.to_string());
+    }
+
+    chunks
+}
 
 /// Checks if the current environment is in development mode.
 ///
@@ -239,3 +591,124 @@ pub fn get_activity(r#type: &StatusType, status_msg: &str) -> ActivityData {
         StatusType::Playing   => ActivityData::playing(status_msg),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::db::{memory::MemoryDatabase, repositories::StatusRepository};
+
+    const GUILD: GuildId = GuildId::new(1);
+
+    /// The prefixes of the guild in the database and in the prefixes map.
+    async fn stored_prefixes(handler: &Handler) -> (Vec<String>, Vec<String>) {
+        let stored = handler
+            .db
+            .list_prefixes()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|row| row.server_id == GUILD.to_string())
+            .map(|row| row.prefix)
+            .collect();
+        let cached = handler
+            .prefixes
+            .read()
+            .await
+            .get(&GUILD.to_string())
+            .cloned()
+            .unwrap_or_default();
+
+        (stored, cached)
+    }
+
+    fn prefixes(values: &[&str]) -> (Vec<String>, Vec<String>) {
+        let values = values.iter().map(ToString::to_string).collect::<Vec<_>>();
+        (values.clone(), values)
+    }
+
+    #[tokio::test]
+    async fn registers_the_default_prefix() {
+        let handler = Handler::for_tests(MemoryDatabase::default());
+
+        register_prefix(GUILD, &handler).await.unwrap();
+
+        assert_eq!(stored_prefixes(&handler).await, prefixes(&["h!"]));
+    }
+
+    #[tokio::test]
+    async fn adds_each_prefix_once() {
+        let handler = Handler::for_tests(MemoryDatabase::default());
+        register_prefix(GUILD, &handler).await.unwrap();
+
+        add_prefix(GUILD, "!", &handler).await.unwrap();
+        add_prefix(GUILD, "!", &handler).await.unwrap();
+
+        assert_eq!(stored_prefixes(&handler).await, prefixes(&["h!", "!"]));
+    }
+
+    #[tokio::test]
+    async fn set_prefix_replaces_all_prefixes() {
+        let handler = Handler::for_tests(MemoryDatabase::default());
+        add_prefix(GUILD, "!", &handler).await.unwrap();
+        add_prefix(GUILD, "?", &handler).await.unwrap();
+
+        set_prefix(GUILD, "h.", &handler).await.unwrap();
+
+        assert_eq!(stored_prefixes(&handler).await, prefixes(&["h."]));
+    }
+
+    #[tokio::test]
+    async fn removes_prefixes() {
+        let handler = Handler::for_tests(MemoryDatabase::default());
+        add_prefix(GUILD, "!", &handler).await.unwrap();
+        add_prefix(GUILD, "?", &handler).await.unwrap();
+
+        assert!(remove_prefix(GUILD, "!", &handler).await.unwrap());
+        assert!(!remove_prefix(GUILD, "!", &handler).await.unwrap());
+
+        assert_eq!(stored_prefixes(&handler).await, prefixes(&["?"]));
+    }
+
+    #[tokio::test]
+    async fn picks_only_active_statuses() {
+        let db = MemoryDatabase::default();
+        let playing = db
+            .add_status(&StatusType::Playing, "with Rust")
+            .await
+            .unwrap();
+        let disabled = db.add_status(&StatusType::Watching, "you").await.unwrap();
+        let ended = db
+            .add_status(&StatusType::Listening, "music")
+            .await
+            .unwrap();
+        db.set_status_weight(disabled, 0).await.unwrap();
+        db.set_status_schedule(ended, None, None, Some(0))
+            .await
+            .unwrap();
+
+        let statuses: StatusVec = RwLock::new(db.list_statuses().await.unwrap());
+
+        for _ in 0..20 {
+            let status = random_active_status(&statuses, Utc::now()).await.unwrap();
+            assert_eq!(status.id, playing);
+        }
+    }
+
+    #[tokio::test]
+    async fn picks_no_status_if_none_are_active() {
+        let db = MemoryDatabase::default();
+        let weekend = db.add_status(&StatusType::Playing, "games").await.unwrap();
+        db.set_status_schedule(weekend, Some("sat,sun"), None, None)
+            .await
+            .unwrap();
+
+        let statuses: StatusVec = RwLock::new(db.list_statuses().await.unwrap());
+        let monday = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let saturday = Utc.with_ymd_and_hms(2024, 1, 6, 12, 0, 0).unwrap();
+
+        assert!(random_active_status(&statuses, monday).await.is_none());
+        assert!(random_active_status(&statuses, saturday).await.is_some());
+    }
+}
@@ -1,3 +1,5 @@
+mod apis;
+mod cli;
 mod commands;
 mod config;
 mod db;
@@ -7,38 +9,57 @@ mod helpers;
 #[macro_use]
 extern crate log;
 
-use std::{collections::HashMap, env, io::Write, process};
+use std::{
+    collections::HashMap,
+    env,
+    io::Write,
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock as SyncRwLock,
+    },
+};
 
 use anyhow::Result;
 use chrono::{format::strftime::StrftimeItems, Utc};
-use db::models::{Prefix, Status};
 use dotenvy::dotenv;
 use log::{Level, LevelFilter};
 use pretty_env_logger::{env_logger::fmt::Color, formatted_builder};
 use serenity::{async_trait, model::prelude::*, prelude::*, Client as DiscordClient};
-use sqlx::SqlitePool;
 use tokio::sync::RwLock;
 
 use crate::{
+    apis::{
+        exchange::ExchangeClient,
+        imgur::ImgurClient,
+        reddit::{start_reddit_refresh_loop, RedditClient},
+    },
     config::Config,
-    handlers::messages::handle_message,
+    handlers::{interactions::handle_command_interaction, messages::handle_message},
     helpers::{
-        types::Handler,
-        utils::{error_log, is_indev, start_status_loop},
+        cooldowns::Cooldowns,
+        types::{CommandSource, Handler, LeetMap},
+        utils::{
+            error_log, is_indev, register_slash_commands, start_backup_loop,
+            start_reload_signal_loop, start_status_loop,
+        },
     },
 };
 
 #[async_trait]
-impl EventHandler for Handler<'_> {
+impl EventHandler for Handler {
     async fn message(&self, ctx: Context, msg: Message) {
         match handle_message(self, &ctx, &msg).await {
             Ok(_) => (),
             Err(e) => {
-                match error_log(&msg, &e, &ctx, self).await {
-                    Ok(_) => (),
-                    Err(e) => error!("Failed to log error, {e}"),
-                }
-                match msg.channel_id.say(&ctx.http, e.to_string()).await {
+                let reply = match error_log(CommandSource::Message(&msg), &e, &ctx, self).await {
+                    Ok(id) => format!("{e}\nError Id: `{id}`"),
+                    Err(log_err) => {
+                        error!("Failed to log error, {log_err}");
+                        e.to_string()
+                    }
+                };
+                match msg.channel_id.say(&ctx.http, reply).await {
                     Ok(_) => (),
                     Err(e) => error!("Failed to send message, {e}"),
                 };
@@ -46,6 +67,26 @@ impl EventHandler for Handler<'_> {
         }
     }
 
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        let Interaction::Command(command) = interaction else {
+            return;
+        };
+
+        if let Err(e) = handle_command_interaction(self, &ctx, &command).await {
+            let source = CommandSource::Interaction(&command);
+            let reply = match error_log(source, &e, &ctx, self).await {
+                Ok(id) => format!("{e}\nError Id: `{id}`"),
+                Err(log_err) => {
+                    error!("Failed to log error, {log_err}");
+                    e.to_string()
+                }
+            };
+            if let Err(e) = source.reply(&ctx, reply).await {
+                error!("Failed to send message, {e}");
+            }
+        }
+    }
+
     async fn ready(&self, ctx: Context, ready: Ready) {
         let date_format = StrftimeItems::new("%d/%m/%Y %H:%M:%S UTC");
         let done_loading_time = Utc::now();
@@ -61,7 +102,16 @@ impl EventHandler for Handler<'_> {
         info!("{}", ready.user.id);
         info!("------------------");
 
-        let status_loop = start_status_loop(&self.statuses, ctx);
+        register_slash_commands(&ctx, &self.config()).await;
+
+        if self.loops_started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let reload_loop = start_reload_signal_loop(self, ctx.clone());
+        let status_loop = start_status_loop(self, ctx);
+        let reddit_loop = start_reddit_refresh_loop(self);
+        let backup_loop = start_backup_loop(self);
 
         if is_indev() {
             info!("Running in dev mode");
@@ -69,7 +119,7 @@ impl EventHandler for Handler<'_> {
             info!("Running in production mode");
         }
 
-        futures::join!(status_loop);
+        futures::join!(status_loop, reddit_loop, reload_loop, backup_loop);
     }
 }
 
@@ -110,37 +160,52 @@ async fn main() -> Result<()> {
         })
         .init();
 
-    let token = env::var("BOT_TOKEN").unwrap_or_else(|_| {
-        error!("Expected a bot token under BOT_TOKEN in the environment");
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if !args.is_empty() {
+        return cli::run(&args).await;
+    }
+
+    let config = Config::load().unwrap_or_else(|e| {
+        error!("{e}");
         process::exit(1);
     });
-    let intents = GatewayIntents::all();
-    let config = Config::new();
+    config.report_missing_credentials();
+
+    let token = config.bot_token.clone();
+    let intents = config.intents;
 
-    let db_pool = SqlitePool::connect(&env::var("DATABASE_URL")?).await?;
+    let db = db::connect(&env::var("DATABASE_URL")?).await?;
 
-    #[allow(unused_mut)]
-    let mut statuses = sqlx::query_as!(Status, "SELECT * FROM statuses")
-        .fetch_all(&db_pool)
-        .await?;
+    let statuses = db.list_statuses().await?;
 
-    let mut prefixes: HashMap<String, String> = HashMap::new();
+    let mut prefixes: HashMap<String, Vec<String>> = HashMap::new();
+
+    for prefix in db.list_prefixes().await? {
+        prefixes
+            .entry(prefix.server_id)
+            .or_default()
+            .push(prefix.prefix);
+    }
 
-    let prefix_arr = sqlx::query_as!(Prefix, "SELECT * FROM prefixes")
-        .fetch_all(&db_pool)
-        .await?;
+    let mut leet: LeetMap = HashMap::new();
 
-    for prefix in prefix_arr {
-        prefixes.insert(prefix.server_id, prefix.prefix);
+    for row in db.list_leet().await? {
+        leet.entry(row.source).or_default().push(row.translated);
     }
 
     let mut client = DiscordClient::builder(token, intents)
         .event_handler(Handler {
             start_time,
-            config,
-            db_pool,
+            config: SyncRwLock::new(Arc::new(config)),
+            db,
             statuses: RwLock::new(statuses),
             prefixes: RwLock::new(prefixes),
+            leet,
+            reddit: RedditClient::new(),
+            exchange: ExchangeClient::new(),
+            imgur: ImgurClient::new(),
+            cooldowns: Cooldowns::default(),
+            loops_started: AtomicBool::new(false),
         })
         .await
         .unwrap_or_else(|err| {